@@ -15,25 +15,113 @@ CREATE TABLE IF NOT EXISTS users (
     email VARCHAR(127) NOT NULL UNIQUE,
     password_hash TEXT NOT NULL,
     created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    /* public URL-safe name of the fronting website. validated before inserting. */
+    website_slug TEXT UNIQUE,
     
     wait_seconds INTEGER CHECK (wait_seconds > 0),
+    /* [{ "platform": "VRChat", "seconds": 120 }] overrides of wait_seconds. the server enforces a minimum per platform. */
+    platform_update_intervals JSONB,
     request_timeout INTEGER CHECK (request_timeout > 0),
     
     system_name TEXT,
+    fronting_source TEXT,
     status_prefix TEXT,
     status_no_fronts TEXT,
     status_truncate_names_to INTEGER CHECK (status_truncate_names_to > 0),
+    /* ordered lists of templates. the first one within the platforms' length limit is used. */
+    status_templates_vrchat TEXT[],
+    status_templates_discord_status_message TEXT[],
+    /* member or custom front IDs or names, which are never shown on the website. */
+    website_hidden_fronters TEXT[],
+    /* look of the website */
+    website_theme TEXT,
+    website_layout TEXT,
+    website_show_avatars BOOLEAN,
+    website_show_member_colors BOOLEAN,
+    website_custom_css TEXT,
+    /* [{ "guild_id": "...", "template": "..." }] servers, in which the discord bot sets the nickname. */
+    discord_nickname_guilds JSONB,
+    /* VRChat OSC is only reachable, when running locally */
+    vrchat_osc_host TEXT,
+    vrchat_osc_port INTEGER CHECK (vrchat_osc_port > 0 AND vrchat_osc_port < 65536),
+    /* [{ "fronter": "...", "parameter": "..." }] */
+    vrchat_osc_avatar_parameters JSONB,
+    /* [{ "fronter": "...", "avatar_id": "avtr_..." }] */
+    vrchat_avatars JSONB,
+    vrchat_avatar_cofront_policy TEXT,
+    /* the fronting section in the VRChat bio */
+    vrchat_bio_template TEXT,
+    /* [{ "fronter": "...", "state": "Busy" }] */
+    vrchat_status_states JSONB,
     
     enable_discord_status_message BOOLEAN NOT NULL DEFAULT false,
     enable_vrchat BOOLEAN NOT NULL DEFAULT false,
+    enable_website BOOLEAN NOT NULL DEFAULT false,
+    enable_discord_nickname BOOLEAN NOT NULL DEFAULT false,
+    enable_vrchat_osc BOOLEAN NOT NULL DEFAULT false,
     
     /* encrypted secrets. need to be re-encrypted, when password changes. */
     enc__simply_plural_token bytea,
     enc__discord_status_message_token bytea,
     enc__vrchat_username bytea,
     enc__vrchat_password bytea,
-    enc__vrchat_cookie bytea
+    enc__vrchat_cookie bytea,
+    enc__pluralkit_token bytea,
+    enc__website_access_token bytea
 
     /* constraints to check manually before inserting into db:
     whenever a platform is enabled, the corresponding fields must be not null.*/
 );
+
+/* previous website slugs of users. they redirect to the current slug until they expire. */
+CREATE TABLE IF NOT EXISTS website_slug_redirects (
+    slug TEXT PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+/* history of the updater cycles per user and platform. */
+CREATE TABLE IF NOT EXISTS updater_events (
+    id BIGSERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    platform TEXT NOT NULL,
+    fronters TEXT[] NOT NULL,
+    status_string TEXT,
+    success BOOLEAN NOT NULL,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS updater_events_by_user_and_time ON updater_events (user_id, created_at DESC);
+
+CREATE TABLE IF NOT EXISTS front_history (
+    id BIGSERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    fronter_id TEXT NOT NULL,
+    fronter_name TEXT NOT NULL,
+    start_time TIMESTAMPTZ NOT NULL,
+    end_time TIMESTAMPTZ -- NULL while still fronting
+);
+
+CREATE INDEX IF NOT EXISTS front_history_by_user_and_time ON front_history (user_id, start_time DESC);
+
+-- resized avatars served by the fronting page instead of the original (third party) URLs
+CREATE TABLE IF NOT EXISTS avatar_cache (
+    source_url TEXT PRIMARY KEY,
+    image BYTEA NOT NULL, -- always PNG
+    fetched_at TIMESTAMPTZ NOT NULL
+);
+
+-- discord accounts linked via a code, which the user got from us and sent to the discord bot
+CREATE TABLE IF NOT EXISTS discord_links (
+    discord_user_id TEXT PRIMARY KEY,
+    user_id UUID NOT NULL UNIQUE REFERENCES users(id) ON DELETE CASCADE,
+    linked_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS discord_link_codes (
+    code TEXT PRIMARY KEY,
+    user_id UUID NOT NULL UNIQUE REFERENCES users(id) ON DELETE CASCADE,
+    expires_at TIMESTAMPTZ NOT NULL
+);
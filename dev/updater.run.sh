@@ -8,8 +8,5 @@ echo "Done."
 
 echo "Run:"
 
-echo "TODO: set config values json!"
-exit 1
-
-./target/release/sp2any --no-gui
+./target/release/sp2any --local
 
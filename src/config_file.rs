@@ -1,18 +1,25 @@
-use anyhow::Result;
+use crate::database;
+use crate::users::UserConfigDbEntries;
+use anyhow::{anyhow, Result};
 use std::fs::{self, File};
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Name of the default configuration file created next to the executable.
 const DEFAULT_CONFIG_NAME: &str = "sp2any.json";
 
+/// Path of the configuration file for the single-user mode.
+pub fn config_file_path() -> PathBuf {
+    PathBuf::from(DEFAULT_CONFIG_NAME)
+}
+
 /// Ensure that the configuration file exists.
 ///
 /// If the file is missing, it will be created with placeholder content so
 /// that users can fill in the required credentials.  The path to the
 /// configuration file is returned for informational purposes.
 pub fn ensure_config_file() -> Result<PathBuf> {
-    let path = PathBuf::from(DEFAULT_CONFIG_NAME);
+    let path = config_file_path();
 
     if !path.exists() {
         if let Some(parent) = path.parent() {
@@ -21,10 +28,15 @@ pub fn ensure_config_file() -> Result<PathBuf> {
 
         let mut file = File::create(&path)?;
         let default_content = r#"{
-  "simply_plural_token": "",
-  "discord_status_message_token": "",
-  "vrchat_username": "",
-  "vrchat_password": ""
+  "system_name": "",
+  "fronting_source": "SimplyPlural",
+  "enable_discord_status_message": false,
+  "enable_vrchat": true,
+  "simply_plural_token": { "secret": "" },
+  "pluralkit_token": { "secret": "" },
+  "discord_status_message_token": { "secret": "" },
+  "vrchat_username": { "secret": "" },
+  "vrchat_password": { "secret": "" }
 }
 "#;
         file.write_all(default_content.as_bytes())?;
@@ -32,3 +44,70 @@ pub fn ensure_config_file() -> Result<PathBuf> {
 
     Ok(path)
 }
+
+/// Read the local configuration file for the single-user mode.
+///
+/// The file has the same shape as the config sent to `/api/user/config`.
+/// Fields missing in the file fall back to the usual defaults later on.
+pub fn read_config_file(path: &Path) -> Result<UserConfigDbEntries<database::Decrypted>> {
+    let content = fs::read_to_string(path)?;
+
+    let config: UserConfigDbEntries<database::Decrypted> = serde_json::from_str(&content)
+        .map_err(|e| anyhow!("Invalid config file '{}': {e}", path.display()))?;
+
+    let fronting_source_token_missing = [&config.simply_plural_token, &config.pluralkit_token]
+        .iter()
+        .all(|t| t.as_ref().is_none_or(|t| t.secret.is_empty()));
+    if fronting_source_token_missing {
+        return Err(anyhow!(
+            "Please fill in your credentials in '{}' and start again.",
+            path.display()
+        ));
+    }
+
+    Ok(config)
+}
+
+/// Store a renewed `vrchat_cookie` in the configuration file.
+///
+/// Only the cookie is changed. All other values in the file are kept, including
+/// unknown ones, such that nothing the user wrote gets lost.
+pub fn write_vrchat_cookie(path: &Path, cookie: &str) -> Result<()> {
+    let mut config: serde_json::Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let entries = config
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("Invalid config file '{}'", path.display()))?;
+    entries.insert(
+        "vrchat_cookie".to_owned(),
+        serde_json::json!({ "secret": cookie }),
+    );
+
+    fs::write(path, serde_json::to_string_pretty(&config)? + "\n")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_renewed_cookie_is_written_and_other_values_are_kept() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("sp2any-test-{}.json", std::process::id()));
+        fs::write(
+            &path,
+            r#"{ "system_name": "Crew", "vrchat_cookie": { "secret": "auth=old" } }"#,
+        )?;
+
+        write_vrchat_cookie(&path, "auth=new")?;
+
+        let config: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        fs::remove_file(&path)?;
+        assert_eq!(
+            config,
+            serde_json::json!({ "system_name": "Crew", "vrchat_cookie": { "secret": "auth=new" } })
+        );
+
+        Ok(())
+    }
+}
@@ -69,17 +69,42 @@ pub fn downgrade<Secret: secrets::SecretType, C: ConstraintsType>(
     UserConfigDbEntries {
         valid_constraints: Some(InvalidConstraints {}),
         wait_seconds: value.wait_seconds,
+        platform_update_intervals: value.platform_update_intervals.clone(),
         system_name: value.system_name.clone(),
+        fronting_source: value.fronting_source.clone(),
         status_prefix: value.status_prefix.clone(),
         status_no_fronts: value.status_no_fronts.clone(),
         status_truncate_names_to: value.status_truncate_names_to,
+        status_templates_vrchat: value.status_templates_vrchat.clone(),
+        status_templates_discord_status_message: value
+            .status_templates_discord_status_message
+            .clone(),
+        website_hidden_fronters: value.website_hidden_fronters.clone(),
+        website_theme: value.website_theme,
+        website_layout: value.website_layout,
+        website_show_avatars: value.website_show_avatars,
+        website_show_member_colors: value.website_show_member_colors,
+        website_custom_css: value.website_custom_css.clone(),
+        discord_nickname_guilds: value.discord_nickname_guilds.clone(),
+        vrchat_osc_host: value.vrchat_osc_host.clone(),
+        vrchat_osc_port: value.vrchat_osc_port,
+        vrchat_osc_avatar_parameters: value.vrchat_osc_avatar_parameters.clone(),
+        vrchat_avatars: value.vrchat_avatars.clone(),
+        vrchat_avatar_cofront_policy: value.vrchat_avatar_cofront_policy,
+        vrchat_bio_template: value.vrchat_bio_template.clone(),
+        vrchat_status_states: value.vrchat_status_states.clone(),
         enable_discord_status_message: value.enable_discord_status_message,
         enable_vrchat: value.enable_vrchat,
+        enable_website: value.enable_website,
+        enable_discord_nickname: value.enable_discord_nickname,
+        enable_vrchat_osc: value.enable_vrchat_osc,
         simply_plural_token: value.simply_plural_token.clone(),
+        pluralkit_token: value.pluralkit_token.clone(),
         discord_status_message_token: value.discord_status_message_token.clone(),
         vrchat_username: value.vrchat_username.clone(),
         vrchat_password: value.vrchat_password.clone(),
         vrchat_cookie: value.vrchat_cookie.clone(),
+        website_access_token: value.website_access_token.clone(),
     }
 }
 
@@ -91,16 +116,41 @@ pub fn only_use_this_function_to_mark_validation_after_you_have_actually_validat
     UserConfigDbEntries {
         valid_constraints: Some(ValidConstraints {}),
         wait_seconds: value.wait_seconds,
+        platform_update_intervals: value.platform_update_intervals.clone(),
         system_name: value.system_name.clone(),
+        fronting_source: value.fronting_source.clone(),
         status_prefix: value.status_prefix.clone(),
         status_no_fronts: value.status_no_fronts.clone(),
         status_truncate_names_to: value.status_truncate_names_to,
+        status_templates_vrchat: value.status_templates_vrchat.clone(),
+        status_templates_discord_status_message: value
+            .status_templates_discord_status_message
+            .clone(),
+        website_hidden_fronters: value.website_hidden_fronters.clone(),
+        website_theme: value.website_theme,
+        website_layout: value.website_layout,
+        website_show_avatars: value.website_show_avatars,
+        website_show_member_colors: value.website_show_member_colors,
+        website_custom_css: value.website_custom_css.clone(),
+        discord_nickname_guilds: value.discord_nickname_guilds.clone(),
+        vrchat_osc_host: value.vrchat_osc_host.clone(),
+        vrchat_osc_port: value.vrchat_osc_port,
+        vrchat_osc_avatar_parameters: value.vrchat_osc_avatar_parameters.clone(),
+        vrchat_avatars: value.vrchat_avatars.clone(),
+        vrchat_avatar_cofront_policy: value.vrchat_avatar_cofront_policy,
+        vrchat_bio_template: value.vrchat_bio_template.clone(),
+        vrchat_status_states: value.vrchat_status_states.clone(),
         enable_discord_status_message: value.enable_discord_status_message,
         enable_vrchat: value.enable_vrchat,
+        enable_website: value.enable_website,
+        enable_discord_nickname: value.enable_discord_nickname,
+        enable_vrchat_osc: value.enable_vrchat_osc,
         simply_plural_token: value.simply_plural_token.clone(),
+        pluralkit_token: value.pluralkit_token.clone(),
         discord_status_message_token: value.discord_status_message_token.clone(),
         vrchat_username: value.vrchat_username.clone(),
         vrchat_password: value.vrchat_password.clone(),
         vrchat_cookie: value.vrchat_cookie.clone(),
+        website_access_token: value.website_access_token.clone(),
     }
 }
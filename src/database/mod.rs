@@ -1,6 +1,7 @@
 mod constraints;
 mod queries;
 mod secrets;
+mod text_enum_macro;
 
 pub use constraints::*;
 pub use queries::*;
@@ -1,4 +1,5 @@
 use anyhow::{anyhow, Result};
+use serde::Serialize;
 use sha2::{Digest, Sha256};
 use sqlx::{FromRow, PgPool};
 
@@ -7,7 +8,7 @@ use crate::{
     database::secrets,
     users,
     users::UserConfigDbEntries,
-    users::{Email, UserId},
+    users::{Email, UserId, WebsiteSlug},
 };
 
 pub async fn create_user(
@@ -46,17 +47,40 @@ pub async fn get_user(
     sqlx::query_as(
         "SELECT
             wait_seconds,
+            platform_update_intervals,
             system_name,
+            fronting_source,
             status_prefix,
             status_no_fronts,
             status_truncate_names_to,
+            status_templates_vrchat,
+            status_templates_discord_status_message,
+            website_hidden_fronters,
+            website_theme,
+            website_layout,
+            website_show_avatars,
+            website_show_member_colors,
+            website_custom_css,
+            discord_nickname_guilds,
+            vrchat_osc_host,
+            vrchat_osc_port,
+            vrchat_osc_avatar_parameters,
+            vrchat_avatars,
+            vrchat_avatar_cofront_policy,
+            vrchat_bio_template,
+            vrchat_status_states,
             enable_discord_status_message,
             enable_vrchat,
+            enable_website,
+            enable_discord_nickname,
+            enable_vrchat_osc,
             '' AS simply_plural_token,
+            '' AS pluralkit_token,
             '' AS discord_status_message_token,
             '' AS vrchat_username,
             '' AS vrchat_password,
             '' AS vrchat_cookie,
+            '' AS website_access_token,
             false AS valid_constraints
             FROM users WHERE id = $1",
     )
@@ -66,6 +90,7 @@ pub async fn get_user(
     .map_err(|e| anyhow!(e))
 }
 
+#[allow(clippy::too_many_lines)]
 pub async fn set_user_config_secrets(
     db_pool: &PgPool,
     user_id: UserId,
@@ -88,7 +113,30 @@ pub async fn set_user_config_secrets(
             enc__discord_status_message_token = pgp_sym_encrypt($11, $9),
             enc__vrchat_username = pgp_sym_encrypt($12, $9),
             enc__vrchat_password = pgp_sym_encrypt($13, $9),
-            enc__vrchat_cookie = pgp_sym_encrypt($14, $9)
+            enc__vrchat_cookie = pgp_sym_encrypt($14, $9),
+            fronting_source = $15,
+            enc__pluralkit_token = pgp_sym_encrypt($16, $9),
+            status_templates_vrchat = $17,
+            status_templates_discord_status_message = $18,
+            website_hidden_fronters = $19,
+            enable_website = $20,
+            enc__website_access_token = pgp_sym_encrypt($21, $9),
+            website_theme = $22,
+            website_layout = $23,
+            website_show_avatars = $24,
+            website_show_member_colors = $25,
+            website_custom_css = $26,
+            discord_nickname_guilds = $27,
+            enable_discord_nickname = $28,
+            vrchat_osc_host = $29,
+            vrchat_osc_port = $30,
+            vrchat_osc_avatar_parameters = $31,
+            enable_vrchat_osc = $32,
+            vrchat_avatars = $33,
+            vrchat_avatar_cofront_policy = $34,
+            vrchat_bio_template = $35,
+            vrchat_status_states = $36,
+            platform_update_intervals = $37
         WHERE id = $1",
     )
     .bind(user_id.inner)
@@ -115,6 +163,55 @@ pub async fn set_user_config_secrets(
     .bind(config.vrchat_username.as_ref().map(|s| s.secret.clone()))
     .bind(config.vrchat_password.as_ref().map(|s| s.secret.clone()))
     .bind(config.vrchat_cookie.as_ref().map(|s| s.secret.clone()))
+    .bind(config.fronting_source.as_ref().map(ToString::to_string))
+    .bind(config.pluralkit_token.as_ref().map(|s| s.secret.clone()))
+    .bind(&config.status_templates_vrchat)
+    .bind(&config.status_templates_discord_status_message)
+    .bind(&config.website_hidden_fronters)
+    // clients from before the website, nickname and OSC updaters don't send their flags
+    .bind(config.enable_website.unwrap_or_default())
+    .bind(
+        config
+            .website_access_token
+            .as_ref()
+            .map(|s| s.secret.clone()),
+    )
+    .bind(config.website_theme.as_ref().map(ToString::to_string))
+    .bind(config.website_layout.as_ref().map(ToString::to_string))
+    .bind(config.website_show_avatars)
+    .bind(config.website_show_member_colors)
+    .bind(&config.website_custom_css)
+    .bind(
+        config
+            .discord_nickname_guilds
+            .as_ref()
+            .map(sqlx::types::Json),
+    )
+    .bind(config.enable_discord_nickname.unwrap_or_default())
+    .bind(&config.vrchat_osc_host)
+    .bind(config.vrchat_osc_port)
+    .bind(
+        config
+            .vrchat_osc_avatar_parameters
+            .as_ref()
+            .map(sqlx::types::Json),
+    )
+    .bind(config.enable_vrchat_osc.unwrap_or_default())
+    .bind(config.vrchat_avatars.as_ref().map(sqlx::types::Json))
+    .bind(
+        config
+            .vrchat_avatar_cofront_policy
+            .as_ref()
+            .map(ToString::to_string),
+    )
+    .bind(&config.vrchat_bio_template)
+    .bind(config.vrchat_status_states.as_ref().map(sqlx::types::Json))
+    .bind(
+        config
+            .platform_update_intervals
+            .as_ref()
+            .map(sqlx::types::Json),
+    )
     .fetch_optional(db_pool)
     .await
     .map_err(|e| anyhow!(e))?;
@@ -122,6 +219,36 @@ pub async fn set_user_config_secrets(
     Ok(())
 }
 
+/// Only updates the encrypted login columns, such that concurrent changes of other settings are kept.
+pub async fn set_vrchat_credentials(
+    db_pool: &PgPool,
+    user_id: &UserId,
+    username: &str,
+    password: &str,
+    cookie: &str,
+    application_user_secret: &secrets::ApplicationUserSecrets,
+) -> Result<()> {
+    let secrets_key = compute_user_secrets_key(user_id, application_user_secret);
+
+    sqlx::query(
+        "UPDATE users
+        SET
+            enc__vrchat_username = pgp_sym_encrypt($3, $2),
+            enc__vrchat_password = pgp_sym_encrypt($4, $2),
+            enc__vrchat_cookie = pgp_sym_encrypt($5, $2)
+        WHERE id = $1",
+    )
+    .bind(user_id.inner)
+    .bind(&secrets_key.inner)
+    .bind(username)
+    .bind(password)
+    .bind(cookie)
+    .execute(db_pool)
+    .await?;
+
+    Ok(())
+}
+
 pub async fn get_user_secrets(
     db_pool: &PgPool,
     user_id: &UserId,
@@ -132,17 +259,40 @@ pub async fn get_user_secrets(
     sqlx::query_as(
         "SELECT
             wait_seconds,
+            platform_update_intervals,
             system_name,
+            fronting_source,
             status_prefix,
             status_no_fronts,
             status_truncate_names_to,
+            status_templates_vrchat,
+            status_templates_discord_status_message,
+            website_hidden_fronters,
+            website_theme,
+            website_layout,
+            website_show_avatars,
+            website_show_member_colors,
+            website_custom_css,
+            discord_nickname_guilds,
+            vrchat_osc_host,
+            vrchat_osc_port,
+            vrchat_osc_avatar_parameters,
+            vrchat_avatars,
+            vrchat_avatar_cofront_policy,
+            vrchat_bio_template,
+            vrchat_status_states,
             enable_discord_status_message,
             enable_vrchat,
+            enable_website,
+            enable_discord_nickname,
+            enable_vrchat_osc,
             pgp_sym_decrypt(enc__simply_plural_token, $2) AS simply_plural_token,
+            pgp_sym_decrypt(enc__pluralkit_token, $2) AS pluralkit_token,
             pgp_sym_decrypt(enc__discord_status_message_token, $2) AS discord_status_message_token,
             pgp_sym_decrypt(enc__vrchat_username, $2) AS vrchat_username,
             pgp_sym_decrypt(enc__vrchat_password, $2) AS vrchat_password,
             pgp_sym_decrypt(enc__vrchat_cookie, $2) AS vrchat_cookie,
+            pgp_sym_decrypt(enc__website_access_token, $2) AS website_access_token,
             true AS valid_constraints
             FROM users WHERE id = $1",
     )
@@ -181,6 +331,420 @@ pub async fn get_user_info(db_pool: &PgPool, user_id: UserId) -> Result<UserInfo
     .map_err(|e| anyhow!(e))
 }
 
+pub async fn get_website_slug(db_pool: &PgPool, user_id: &UserId) -> Result<Option<WebsiteSlug>> {
+    let slug: Option<String> = sqlx::query_scalar("SELECT website_slug FROM users WHERE id = $1")
+        .bind(user_id.inner)
+        .fetch_one(db_pool)
+        .await
+        .map_err(|e| anyhow!(e))?;
+
+    slug.map(WebsiteSlug::try_from).transpose()
+}
+
+pub async fn get_user_id_by_website_slug(
+    db_pool: &PgPool,
+    slug: &WebsiteSlug,
+) -> Result<Option<UserId>> {
+    sqlx::query_as("SELECT id AS inner FROM users WHERE website_slug = $1")
+        .bind(&slug.inner)
+        .fetch_optional(db_pool)
+        .await
+        .map_err(|e| anyhow!(e))
+}
+
+/// The current slug of the user, who used the given slug previously and which hasn't expired yet.
+pub async fn get_website_slug_redirect(
+    db_pool: &PgPool,
+    slug: &WebsiteSlug,
+) -> Result<Option<WebsiteSlug>> {
+    let current_slug: Option<Option<String>> = sqlx::query_scalar(
+        "SELECT users.website_slug
+            FROM website_slug_redirects JOIN users ON users.id = website_slug_redirects.user_id
+            WHERE website_slug_redirects.slug = $1 AND website_slug_redirects.expires_at > NOW()",
+    )
+    .bind(&slug.inner)
+    .fetch_optional(db_pool)
+    .await
+    .map_err(|e| anyhow!(e))?;
+
+    current_slug
+        .flatten()
+        .map(WebsiteSlug::try_from)
+        .transpose()
+}
+
+/// Sets the new slug and lets the previous slug redirect to it for `redirect_grace_days`.
+pub async fn set_website_slug(
+    db_pool: &PgPool,
+    user_id: &UserId,
+    slug: &WebsiteSlug,
+    redirect_grace_days: i32,
+) -> Result<()> {
+    let mut transaction = db_pool.begin().await?;
+
+    let taken: bool = sqlx::query_scalar(
+        "SELECT
+            EXISTS (SELECT 1 FROM users WHERE website_slug = $1 AND id <> $2)
+            OR EXISTS (
+                SELECT 1 FROM website_slug_redirects
+                WHERE slug = $1 AND user_id <> $2 AND expires_at > NOW()
+            )",
+    )
+    .bind(&slug.inner)
+    .bind(user_id.inner)
+    .fetch_one(&mut *transaction)
+    .await?;
+
+    if taken {
+        return Err(anyhow!("Slug '{}' is already taken.", slug.inner));
+    }
+
+    let previous_slug: Option<String> =
+        sqlx::query_scalar("SELECT website_slug FROM users WHERE id = $1 FOR UPDATE")
+            .bind(user_id.inner)
+            .fetch_one(&mut *transaction)
+            .await?;
+
+    if previous_slug.as_ref() == Some(&slug.inner) {
+        return Ok(());
+    }
+
+    // the slug is free at this point. redirects for it are either our own or expired.
+    sqlx::query("DELETE FROM website_slug_redirects WHERE slug = $1")
+        .bind(&slug.inner)
+        .execute(&mut *transaction)
+        .await?;
+
+    if let Some(previous_slug) = previous_slug {
+        sqlx::query(
+            "INSERT INTO website_slug_redirects (slug, user_id, expires_at)
+            VALUES ($1, $2, NOW() + make_interval(days => $3))",
+        )
+        .bind(previous_slug)
+        .bind(user_id.inner)
+        .bind(redirect_grace_days)
+        .execute(&mut *transaction)
+        .await?;
+    }
+
+    sqlx::query("UPDATE users SET website_slug = $2 WHERE id = $1")
+        .bind(user_id.inner)
+        .bind(&slug.inner)
+        .execute(&mut *transaction)
+        .await?;
+
+    transaction.commit().await.map_err(|e| anyhow!(e))
+}
+
+/// The cached avatar of `source_url`, if it was fetched within the last `max_age_seconds`.
+pub async fn get_cached_avatar(
+    db_pool: &PgPool,
+    source_url: &str,
+    max_age_seconds: i64,
+) -> Result<Option<Vec<u8>>> {
+    sqlx::query_scalar(
+        "SELECT image FROM avatar_cache
+            WHERE source_url = $1 AND fetched_at > NOW() - $2 * INTERVAL '1 second'",
+    )
+    .bind(source_url)
+    .bind(max_age_seconds)
+    .fetch_optional(db_pool)
+    .await
+    .map_err(|e| anyhow!(e))
+}
+
+/// Caches the avatar and removes the avatars older than `max_age_seconds`.
+pub async fn set_cached_avatar(
+    db_pool: &PgPool,
+    source_url: &str,
+    image: &[u8],
+    max_age_seconds: i64,
+) -> Result<()> {
+    let mut transaction = db_pool.begin().await?;
+
+    sqlx::query("DELETE FROM avatar_cache WHERE fetched_at <= NOW() - $1 * INTERVAL '1 second'")
+        .bind(max_age_seconds)
+        .execute(&mut *transaction)
+        .await?;
+
+    sqlx::query(
+        "INSERT INTO avatar_cache (source_url, image, fetched_at) VALUES ($1, $2, NOW())
+            ON CONFLICT (source_url) DO UPDATE SET image = $2, fetched_at = NOW()",
+    )
+    .bind(source_url)
+    .bind(image)
+    .execute(&mut *transaction)
+    .await?;
+
+    transaction.commit().await.map_err(|e| anyhow!(e))
+}
+
+/// Replaces any previous link code of the user with `code`.
+pub async fn set_discord_link_code(
+    db_pool: &PgPool,
+    user_id: &UserId,
+    code: &str,
+    valid_minutes: i32,
+) -> Result<chrono::DateTime<chrono::Utc>> {
+    sqlx::query_scalar(
+        "INSERT INTO discord_link_codes (code, user_id, expires_at)
+            VALUES ($2, $1, NOW() + make_interval(mins => $3))
+            ON CONFLICT (user_id) DO UPDATE SET code = $2, expires_at = NOW() + make_interval(mins => $3)
+            RETURNING expires_at",
+    )
+    .bind(user_id.inner)
+    .bind(code)
+    .bind(valid_minutes)
+    .fetch_one(db_pool)
+    .await
+    .map_err(|e| anyhow!(e))
+}
+
+/// Links the discord account to the user of the code. Returns `None`, if the code is invalid or expired.
+/// A code can only be used once.
+pub async fn link_discord_account(
+    db_pool: &PgPool,
+    code: &str,
+    discord_user_id: &str,
+) -> Result<Option<UserId>> {
+    let mut transaction = db_pool.begin().await?;
+
+    let user_id: Option<sqlx::types::Uuid> = sqlx::query_scalar(
+        "DELETE FROM discord_link_codes WHERE code = $1 AND expires_at > NOW() RETURNING user_id",
+    )
+    .bind(code)
+    .fetch_optional(&mut *transaction)
+    .await?;
+
+    let Some(user_id) = user_id else {
+        return Ok(None);
+    };
+
+    // each discord account and each user have at most one link
+    sqlx::query("DELETE FROM discord_links WHERE discord_user_id = $1 OR user_id = $2")
+        .bind(discord_user_id)
+        .bind(user_id)
+        .execute(&mut *transaction)
+        .await?;
+
+    sqlx::query("INSERT INTO discord_links (discord_user_id, user_id) VALUES ($1, $2)")
+        .bind(discord_user_id)
+        .bind(user_id)
+        .execute(&mut *transaction)
+        .await?;
+
+    transaction.commit().await?;
+
+    Ok(Some(UserId::from(user_id)))
+}
+
+/// Returns whether the discord account was linked.
+pub async fn unlink_discord_account(db_pool: &PgPool, discord_user_id: &str) -> Result<bool> {
+    let result = sqlx::query("DELETE FROM discord_links WHERE discord_user_id = $1")
+        .bind(discord_user_id)
+        .execute(db_pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_user_id_by_discord_user_id(
+    db_pool: &PgPool,
+    discord_user_id: &str,
+) -> Result<Option<UserId>> {
+    let user_id: Option<sqlx::types::Uuid> =
+        sqlx::query_scalar("SELECT user_id FROM discord_links WHERE discord_user_id = $1")
+            .bind(discord_user_id)
+            .fetch_optional(db_pool)
+            .await?;
+
+    Ok(user_id.map(UserId::from))
+}
+
+pub async fn get_discord_user_id_by_user_id(
+    db_pool: &PgPool,
+    user_id: &UserId,
+) -> Result<Option<String>> {
+    sqlx::query_scalar("SELECT discord_user_id FROM discord_links WHERE user_id = $1")
+        .bind(user_id.inner)
+        .fetch_optional(db_pool)
+        .await
+        .map_err(|e| anyhow!(e))
+}
+
+pub async fn insert_updater_event(
+    db_pool: &PgPool,
+    user_id: &UserId,
+    event: &UpdaterEvent,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO updater_events
+            (user_id, platform, fronters, status_string, success, error, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)",
+    )
+    .bind(user_id.inner)
+    .bind(&event.platform)
+    .bind(&event.fronters)
+    .bind(&event.status_string)
+    .bind(event.success)
+    .bind(&event.error)
+    .bind(event.created_at)
+    .execute(db_pool)
+    .await
+    .map(|_| ())
+    .map_err(|e| anyhow!(e))
+}
+
+pub async fn get_updater_events(
+    db_pool: &PgPool,
+    user_id: &UserId,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<UpdaterEvent>> {
+    sqlx::query_as(
+        "SELECT
+            platform,
+            fronters,
+            status_string,
+            success,
+            error,
+            created_at
+            FROM updater_events WHERE user_id = $1
+            ORDER BY created_at DESC, id DESC
+            LIMIT $2 OFFSET $3",
+    )
+    .bind(user_id.inner)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(db_pool)
+    .await
+    .map_err(|e| anyhow!(e))
+}
+
+pub async fn delete_updater_events_older_than(
+    db_pool: &PgPool,
+    user_id: &UserId,
+    retention_days: i32,
+) -> Result<()> {
+    sqlx::query(
+        "DELETE FROM updater_events
+        WHERE user_id = $1 AND created_at < NOW() - make_interval(days => $2)",
+    )
+    .bind(user_id.inner)
+    .bind(retention_days)
+    .execute(db_pool)
+    .await
+    .map(|_| ())
+    .map_err(|e| anyhow!(e))
+}
+
+pub async fn get_open_front_history_entries(
+    db_pool: &PgPool,
+    user_id: &UserId,
+) -> Result<Vec<FrontHistoryEntry>> {
+    sqlx::query_as(
+        "SELECT
+            id,
+            fronter_id,
+            fronter_name,
+            start_time,
+            end_time
+            FROM front_history WHERE user_id = $1 AND end_time IS NULL",
+    )
+    .bind(user_id.inner)
+    .fetch_all(db_pool)
+    .await
+    .map_err(|e| anyhow!(e))
+}
+
+pub async fn insert_front_history_entry(
+    db_pool: &PgPool,
+    user_id: &UserId,
+    fronter_id: &str,
+    fronter_name: &str,
+    start_time: chrono::DateTime<chrono::Utc>,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO front_history (user_id, fronter_id, fronter_name, start_time)
+        VALUES ($1, $2, $3, $4)",
+    )
+    .bind(user_id.inner)
+    .bind(fronter_id)
+    .bind(fronter_name)
+    .bind(start_time)
+    .execute(db_pool)
+    .await
+    .map(|_| ())
+    .map_err(|e| anyhow!(e))
+}
+
+pub async fn close_front_history_entry(
+    db_pool: &PgPool,
+    user_id: &UserId,
+    entry_id: i64,
+    end_time: chrono::DateTime<chrono::Utc>,
+) -> Result<()> {
+    sqlx::query("UPDATE front_history SET end_time = $3 WHERE user_id = $1 AND id = $2")
+        .bind(user_id.inner)
+        .bind(entry_id)
+        .bind(end_time)
+        .execute(db_pool)
+        .await
+        .map(|_| ())
+        .map_err(|e| anyhow!(e))
+}
+
+pub async fn get_front_history_entries(
+    db_pool: &PgPool,
+    user_id: &UserId,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<FrontHistoryEntry>> {
+    sqlx::query_as(
+        "SELECT
+            id,
+            fronter_id,
+            fronter_name,
+            start_time,
+            end_time
+            FROM front_history WHERE user_id = $1
+            ORDER BY start_time DESC, id DESC
+            LIMIT $2 OFFSET $3",
+    )
+    .bind(user_id.inner)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(db_pool)
+    .await
+    .map_err(|e| anyhow!(e))
+}
+
+/// All front history entries overlapping with the time range from `from` until `until`.
+pub async fn get_front_history_entries_between(
+    db_pool: &PgPool,
+    user_id: &UserId,
+    from: chrono::DateTime<chrono::Utc>,
+    until: chrono::DateTime<chrono::Utc>,
+) -> Result<Vec<FrontHistoryEntry>> {
+    sqlx::query_as(
+        "SELECT
+            id,
+            fronter_id,
+            fronter_name,
+            start_time,
+            end_time
+            FROM front_history
+            WHERE user_id = $1 AND start_time < $3 AND (end_time IS NULL OR end_time > $2)
+            ORDER BY start_time ASC, id ASC",
+    )
+    .bind(user_id.inner)
+    .bind(from)
+    .bind(until)
+    .fetch_all(db_pool)
+    .await
+    .map_err(|e| anyhow!(e))
+}
+
 fn compute_user_secrets_key(
     user_id: &UserId,
     application_user_secret: &secrets::ApplicationUserSecrets,
@@ -207,3 +771,22 @@ pub struct UserInfo {
     pub password_hash: users::PasswordHashString,
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
+
+#[derive(FromRow, Serialize, Clone, Debug)]
+pub struct UpdaterEvent {
+    pub platform: String,
+    pub fronters: Vec<String>,
+    pub status_string: Option<String>,
+    pub success: bool,
+    pub error: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(FromRow, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct FrontHistoryEntry {
+    pub id: i64,
+    pub fronter_id: String,
+    pub fronter_name: String,
+    pub start_time: chrono::DateTime<chrono::Utc>,
+    pub end_time: Option<chrono::DateTime<chrono::Utc>>,
+}
@@ -0,0 +1,27 @@
+/// Implements `Type<Postgres>` and `Decode` for an enum, which is stored as plain text.
+/// The text is written via `Display` and read via `FromStr`, i.e. `strum_macros::Display` and `EnumString`.
+#[macro_export]
+macro_rules! sqlx_text_enum {
+    ($enum:ident) => {
+        impl sqlx::Type<sqlx::Postgres> for $enum {
+            fn type_info() -> sqlx::postgres::PgTypeInfo {
+                <String as sqlx::Type<sqlx::Postgres>>::type_info()
+            }
+
+            fn compatible(ty: &sqlx::postgres::PgTypeInfo) -> bool {
+                <String as sqlx::Type<sqlx::Postgres>>::compatible(ty)
+            }
+        }
+
+        impl<'r> sqlx::Decode<'r, sqlx::Postgres> for $enum {
+            fn decode(
+                value: sqlx::postgres::PgValueRef<'r>,
+            ) -> Result<Self, sqlx::error::BoxDynError> {
+                let text = <String as sqlx::Decode<sqlx::Postgres>>::decode(value)?;
+
+                text.parse::<Self>()
+                    .map_err(|_| format!("Unknown {} '{text}'", stringify!($enum)).into())
+            }
+        }
+    };
+}
@@ -1 +1,52 @@
 pub type HttpResult<T> = Result<T, rocket::response::Debug<anyhow::Error>>;
+
+const DEFAULT_PAGE_SIZE: u32 = 50;
+const MAX_PAGE_SIZE: u32 = 200;
+
+/// The requested page of a paginated endpoint with the page size clamped to the allowed range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pagination {
+    pub page: u32,
+    pub page_size: u32,
+}
+
+impl Pagination {
+    pub fn new(page: Option<u32>, page_size: Option<u32>) -> Self {
+        Self {
+            page: page.unwrap_or(0),
+            page_size: page_size
+                .unwrap_or(DEFAULT_PAGE_SIZE)
+                .clamp(1, MAX_PAGE_SIZE),
+        }
+    }
+
+    pub fn limit(self) -> i64 {
+        self.page_size.into()
+    }
+
+    pub fn offset(self) -> i64 {
+        i64::from(self.page) * i64::from(self.page_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_size_is_clamped() {
+        assert_eq!(
+            Pagination::new(None, None),
+            Pagination {
+                page: 0,
+                page_size: DEFAULT_PAGE_SIZE,
+            }
+        );
+        assert_eq!(Pagination::new(None, Some(0)).page_size, 1);
+        assert_eq!(Pagination::new(None, Some(10_000)).page_size, MAX_PAGE_SIZE);
+
+        let pagination = Pagination::new(Some(3), Some(20));
+        assert_eq!(pagination.limit(), 20);
+        assert_eq!(pagination.offset(), 60);
+    }
+}
@@ -17,37 +17,85 @@ mod users;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Ensure that the local configuration file exists before continuing so
-    // the executable can be run without manually creating it beforehand.
-    let _ = config_file::ensure_config_file()?;
-
     let cli_args = setup::CliArgs::parse();
 
+    if cli_args.local {
+        return run_local_updater(&cli_args).await;
+    }
+
     let app_setup = setup::application_setup(&cli_args).await?;
 
     let () = updater::api::restart_all_user_updaters_for_app_startups(app_setup.clone()).await?;
 
+    if let Some(discord_bot_token) = cli_args.discord_bot_token.clone() {
+        start_discord_bot(discord_bot_token, app_setup.clone());
+    }
+
     run_webserver(app_setup).await
 }
 
+async fn run_local_updater(cli_args: &setup::CliArgs) -> Result<()> {
+    // Ensure that the local configuration file exists before continuing so
+    // the executable can be run without manually creating it beforehand.
+    let config_path = config_file::ensure_config_file()?;
+
+    let db_config = config_file::read_config_file(&config_path)?;
+
+    let client = setup::create_http_client(cli_args)?;
+
+    let local_user_id = users::UserId::from(sqlx::types::Uuid::nil());
+
+    let (config, _) =
+        users::create_config_with_strong_constraints(&local_user_id, &client, &db_config)?;
+
+    let shared_updaters = updater::UpdaterManager::new(cli_args, None);
+
+    updater::run_loop(config, shared_updaters).await
+}
+
+fn start_discord_bot(discord_bot_token: String, setup: setup::ApplicationSetup) {
+    let bot = platforms::DiscordBot {
+        db_pool: setup.db_pool,
+        application_user_secrets: setup.application_user_secrets,
+        client: setup.client,
+        shared_updaters: setup.shared_updaters,
+    };
+
+    tokio::spawn(async move {
+        // the webserver keeps on running without the bot
+        if let Err(e) = platforms::run_discord_bot(&discord_bot_token, bot).await {
+            eprintln!("Discord bot stopped: {e}");
+        }
+    });
+}
+
 async fn run_webserver(setup: setup::ApplicationSetup) -> Result<()> {
     let _ = rocket::build()
         .manage(setup.db_pool)
         .manage(setup.jwt_secret)
         .manage(setup.application_user_secrets)
         .manage(setup.client)
+        .manage(setup.avatar_client)
         .manage(setup.shared_updaters)
+        .manage(setup.vrchat_pending_auths)
         .mount(
             "/",
             routes![
                 users::user_api::post_api_user_register,
                 users::user_api::post_api_user_login,
                 users::user_api::get_api_user_info,
+                users::user_api::post_api_user_website_slug,
                 users::config_api::get_api_user_config,
                 users::config_api::post_api_user_config,
                 updater::api::get_api_updaters_status,
                 updater::api::post_api_updaters_restart,
-                platforms::webview_api::get_api_fronting_by_user_id,
+                updater::api::get_api_updaters_history,
+                plurality::front_history_api::get_api_fronting_history,
+                plurality::front_history_api::get_api_fronting_stats,
+                platforms::webview_api::get_fronting_by_slug,
+                platforms::webview_api::get_fronting_events_by_slug,
+                platforms::webview_api::get_fronting_avatar_by_slug,
+                platforms::discord_bot_api::post_api_user_platform_discord_link_code,
                 platforms::vrchat_api::post_api_user_platform_vrchat_auth_2fa_request,
                 platforms::vrchat_api::post_api_user_platform_vrchat_auth_2fa_resolve
             ],
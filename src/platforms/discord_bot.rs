@@ -0,0 +1,431 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serenity::all::{
+    Command, CommandData, CommandDataOptionValue, CommandInteraction, CommandOptionType, Context,
+    CreateAllowedMentions, CreateCommand, CreateCommandOption, CreateEmbed,
+    CreateInteractionResponse, CreateInteractionResponseMessage, EventHandler, GatewayIntents,
+    Interaction, Ready,
+};
+use sqlx::PgPool;
+
+use crate::database;
+use crate::platforms::webview_api;
+use crate::plurality;
+use crate::updater;
+use crate::users;
+
+const COMMAND_FRONTING: &str = "fronting";
+const COMMAND_LINK: &str = "link";
+const COMMAND_UNLINK: &str = "unlink";
+
+const OPTION_USER: &str = "user";
+const OPTION_CODE: &str = "code";
+
+/// A slash command sent to the bot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BotCommand {
+    /// Show the current fronters of the sp2any user linked to the discord user.
+    Fronting {
+        discord_user_id: String,
+    },
+    /// Link the invoking discord user to the sp2any user, who created the code.
+    Link {
+        code: String,
+    },
+    Unlink,
+}
+
+impl BotCommand {
+    /// Without a mentioned user, `/fronting` shows the fronters of the invoking user.
+    pub fn parse(data: &CommandData, invoking_discord_user_id: &str) -> Option<Self> {
+        match data.name.as_str() {
+            COMMAND_FRONTING => {
+                let mentioned = data.options.iter().find_map(|o| match &o.value {
+                    CommandDataOptionValue::User(id) if o.name == OPTION_USER => {
+                        Some(id.to_string())
+                    }
+                    _ => None,
+                });
+                Some(Self::Fronting {
+                    discord_user_id: mentioned
+                        .unwrap_or_else(|| invoking_discord_user_id.to_owned()),
+                })
+            }
+            COMMAND_LINK => data.options.iter().find_map(|o| match &o.value {
+                CommandDataOptionValue::String(code) if o.name == OPTION_CODE => Some(Self::Link {
+                    code: code.trim().to_owned(),
+                }),
+                _ => None,
+            }),
+            COMMAND_UNLINK => Some(Self::Unlink),
+            _ => None,
+        }
+    }
+}
+
+pub fn bot_commands() -> Vec<CreateCommand> {
+    vec![
+        CreateCommand::new(COMMAND_FRONTING)
+            .description("Show who is currently fronting")
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::User,
+                    OPTION_USER,
+                    "Whose fronters to show. Defaults to yourself.",
+                )
+                .required(false),
+            ),
+        CreateCommand::new(COMMAND_LINK)
+            .description("Link your Discord account to your SP2Any account")
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    OPTION_CODE,
+                    "The link code from SP2Any",
+                )
+                .required(true),
+            ),
+        CreateCommand::new(COMMAND_UNLINK).description("Unlink your Discord account from SP2Any"),
+    ]
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BotReply {
+    Fronting(FrontingEmbed),
+    /// Only shown to the invoking user.
+    Notice(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrontingEmbed {
+    pub title: String,
+    pub description: String,
+    pub color: Option<u32>,
+    pub thumbnail_url: Option<String>,
+    pub fetched_at: DateTime<Utc>,
+}
+
+/// The embed shows the fronters with the color and avatar of the first one.
+pub fn fronting_embed(
+    config: &users::UserConfigForUpdater,
+    fronts: &[plurality::Fronter],
+    fetched_at: DateTime<Utc>,
+) -> FrontingEmbed {
+    let description = if fronts.is_empty() {
+        config.status_no_fronts.clone()
+    } else {
+        fronts
+            .iter()
+            .map(|f| {
+                // discord renders these as relative times in the viewers' locale
+                f.start_time.map_or_else(
+                    || format!("**{}**", f.name),
+                    |start_time| format!("**{}** since <t:{}:R>", f.name, start_time.timestamp()),
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    };
+
+    let first = fronts.first();
+
+    FrontingEmbed {
+        title: format!("{} - Fronting Status", config.system_name),
+        description,
+        color: first.and_then(|f| f.color.as_deref()).and_then(color_value),
+        thumbnail_url: first
+            .map(|f| f.avatar_url.clone())
+            .filter(|url| !url.is_empty()),
+        fetched_at,
+    }
+}
+
+/// Only fronters which are public on the website are shared by the bot.
+pub fn shares_fronting(config: &users::UserConfigForUpdater) -> bool {
+    config.enable_website
+        && webview_api::is_access_allowed(&config.website_access_token.secret, None)
+}
+
+// colors are normalized to "#rgb", "#rrggbb" or "#rrggbbaa". discord has no transparency.
+fn color_value(color: &str) -> Option<u32> {
+    let hex = color.trim_start_matches('#');
+    let rgb = match hex.len() {
+        3 => hex.chars().flat_map(|c| [c, c]).collect(),
+        6 | 8 => hex[..6].to_owned(),
+        _ => return None,
+    };
+    u32::from_str_radix(&rgb, 16).ok()
+}
+
+impl From<BotReply> for CreateInteractionResponse {
+    fn from(reply: BotReply) -> Self {
+        let message = match reply {
+            BotReply::Fronting(embed) => {
+                let mut create_embed = CreateEmbed::new()
+                    .title(embed.title)
+                    .description(embed.description)
+                    .timestamp(embed.fetched_at);
+                if let Some(color) = embed.color {
+                    create_embed = create_embed.color(color);
+                }
+                if let Some(thumbnail_url) = embed.thumbnail_url {
+                    create_embed = create_embed.thumbnail(thumbnail_url);
+                }
+                CreateInteractionResponseMessage::new().embed(create_embed)
+            }
+            BotReply::Notice(text) => CreateInteractionResponseMessage::new()
+                .content(text)
+                .ephemeral(true),
+        };
+
+        // member names should never ping anyone
+        Self::Message(message.allowed_mentions(CreateAllowedMentions::new()))
+    }
+}
+
+/// Answers the slash commands. Shared by all users, as there is one bot per deployment.
+pub struct DiscordBot {
+    pub db_pool: PgPool,
+    pub application_user_secrets: database::ApplicationUserSecrets,
+    pub client: reqwest::Client,
+    pub shared_updaters: updater::UpdaterManager,
+}
+
+impl DiscordBot {
+    pub async fn handle(
+        &self,
+        command: BotCommand,
+        invoking_discord_user_id: &str,
+    ) -> Result<BotReply> {
+        match command {
+            BotCommand::Fronting { discord_user_id } => self.fronting(&discord_user_id).await,
+            BotCommand::Link { code } => {
+                let linked =
+                    database::link_discord_account(&self.db_pool, &code, invoking_discord_user_id)
+                        .await?;
+                Ok(BotReply::Notice(if linked.is_some() {
+                    "Your Discord account is now linked to SP2Any.".to_owned()
+                } else {
+                    "This link code is invalid or expired. Please request a new one.".to_owned()
+                }))
+            }
+            BotCommand::Unlink => {
+                let unlinked =
+                    database::unlink_discord_account(&self.db_pool, invoking_discord_user_id)
+                        .await?;
+                Ok(BotReply::Notice(if unlinked {
+                    "Your Discord account is no longer linked to SP2Any.".to_owned()
+                } else {
+                    "Your Discord account isn't linked to SP2Any.".to_owned()
+                }))
+            }
+        }
+    }
+
+    async fn reply_to(&self, interaction: &CommandInteraction) -> BotReply {
+        let invoking_discord_user_id = interaction.user.id.to_string();
+
+        let Some(command) = BotCommand::parse(&interaction.data, &invoking_discord_user_id) else {
+            return BotReply::Notice("Unknown command.".to_owned());
+        };
+
+        eprintln!("Discord bot: {command:?}");
+
+        self.handle(command, &invoking_discord_user_id)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("Discord bot: Error: {e}");
+                BotReply::Notice("Something went wrong. Please try again later.".to_owned())
+            })
+    }
+
+    async fn fronting(&self, discord_user_id: &str) -> Result<BotReply> {
+        let Some(user_id) =
+            database::get_user_id_by_discord_user_id(&self.db_pool, discord_user_id).await?
+        else {
+            return Ok(BotReply::Notice(format!(
+                "<@{discord_user_id}> hasn't linked their Discord account to SP2Any."
+            )));
+        };
+
+        let user_config =
+            database::get_user_secrets(&self.db_pool, &user_id, &self.application_user_secrets)
+                .await?;
+        let (config, _) =
+            users::create_config_with_strong_constraints(&user_id, &self.client, &user_config)?;
+
+        if !shares_fronting(&config) {
+            return Ok(BotReply::Notice(format!(
+                "<@{discord_user_id}> doesn't share their fronters publicly."
+            )));
+        }
+
+        let cached_fronts = self
+            .shared_updaters
+            .get_or_fetch_fronts(&user_id, &config)
+            .await?;
+
+        // the same fronters as on the website are hidden
+        let visible_fronts =
+            webview_api::visible_fronts(&cached_fronts.fronts, &config.website_hidden_fronters);
+
+        Ok(BotReply::Fronting(fronting_embed(
+            &config,
+            &visible_fronts,
+            cached_fronts.fetched_at,
+        )))
+    }
+}
+
+#[serenity::async_trait]
+impl EventHandler for DiscordBot {
+    async fn ready(&self, ctx: Context, ready: Ready) {
+        eprintln!("Discord bot connected as {}.", ready.user.name);
+
+        if let Err(e) = Command::set_global_commands(&ctx.http, bot_commands()).await {
+            eprintln!("Discord bot: Failed to register slash commands: {e}");
+        }
+    }
+
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        let Interaction::Command(interaction) = interaction else {
+            return;
+        };
+
+        let reply = self.reply_to(&interaction).await;
+
+        if let Err(e) = interaction.create_response(&ctx.http, reply.into()).await {
+            eprintln!(
+                "Discord bot: Failed to respond to /{}: {e}",
+                interaction.data.name
+            );
+        }
+    }
+}
+
+/// Connects to the discord gateway and answers slash commands until the connection ends.
+pub async fn run_discord_bot(bot_token: &str, bot: DiscordBot) -> Result<()> {
+    // slash commands arrive as interactions, which need no privileged intents
+    let mut client = serenity::Client::builder(bot_token, GatewayIntents::empty())
+        .event_handler(bot)
+        .await?;
+
+    client.start().await.map_err(|e| anyhow!(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn command_data(json: serde_json::Value) -> Result<CommandData> {
+        Ok(serde_json::from_value(json)?)
+    }
+
+    #[test]
+    fn test_commands_are_parsed_from_interaction_data() -> Result<()> {
+        let fronting_mentioned = command_data(serde_json::json!({
+            "id": "1", "name": "fronting", "type": 1,
+            "options": [{ "name": "user", "type": 6, "value": "222" }],
+        }))?;
+        assert_eq!(
+            BotCommand::parse(&fronting_mentioned, "111"),
+            Some(BotCommand::Fronting {
+                discord_user_id: "222".to_owned()
+            })
+        );
+
+        let fronting_self = command_data(serde_json::json!({
+            "id": "1", "name": "fronting", "type": 1,
+        }))?;
+        assert_eq!(
+            BotCommand::parse(&fronting_self, "111"),
+            Some(BotCommand::Fronting {
+                discord_user_id: "111".to_owned()
+            })
+        );
+
+        let link = command_data(serde_json::json!({
+            "id": "2", "name": "link", "type": 1,
+            "options": [{ "name": "code", "type": 3, "value": " abc123 " }],
+        }))?;
+        assert_eq!(
+            BotCommand::parse(&link, "111"),
+            Some(BotCommand::Link {
+                code: "abc123".to_owned()
+            })
+        );
+
+        let unknown = command_data(serde_json::json!({
+            "id": "3", "name": "dance", "type": 1,
+        }))?;
+        assert_eq!(BotCommand::parse(&unknown, "111"), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fronting_embed_shows_fronters_with_color_and_avatar_of_first() -> Result<()> {
+        let config = users::config_for_tests(&users::UserConfigDbEntries::default())?;
+        let start_time = Utc.timestamp_opt(1_700_000_000, 0).single();
+        let alice = plurality::Fronter {
+            avatar_url: "https://example.com/a".to_owned(),
+            color: Some("#f80".to_owned()),
+            start_time,
+            ..plurality::Fronter::for_tests("Alice")
+        };
+        let fetched_at = DateTime::<Utc>::default();
+
+        let embed = fronting_embed(
+            &config,
+            &[alice, plurality::Fronter::for_tests("Bob")],
+            fetched_at,
+        );
+
+        assert_eq!(
+            embed,
+            FrontingEmbed {
+                title: "System - Fronting Status".to_owned(),
+                description: "**Alice** since <t:1700000000:R>\n**Bob**".to_owned(),
+                color: Some(0xff_88_00),
+                thumbnail_url: Some("https://example.com/a".to_owned()),
+                fetched_at,
+            }
+        );
+
+        let empty = fronting_embed(&config, &[], fetched_at);
+        assert_eq!(empty.description, config.status_no_fronts);
+        assert_eq!(empty.color, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fronting_is_only_shared_with_a_public_website() -> Result<()> {
+        let db_config =
+            |enable_website, website_access_token: Option<&str>| users::UserConfigDbEntries {
+                enable_website: Some(enable_website),
+                website_access_token: website_access_token.map(Into::into),
+                ..Default::default()
+            };
+
+        let public = users::config_for_tests(&db_config(true, None))?;
+        assert!(shares_fronting(&public));
+
+        let disabled = users::config_for_tests(&db_config(false, None))?;
+        assert!(!shares_fronting(&disabled));
+
+        let protected = users::config_for_tests(&db_config(true, Some("secret")))?;
+        assert!(!shares_fronting(&protected));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_colors_are_converted_to_rgb_values() {
+        assert_eq!(color_value("#ff0000"), Some(0xff_00_00));
+        assert_eq!(color_value("#00ff0080"), Some(0x00_ff_00));
+        assert_eq!(color_value("#abc"), Some(0xaa_bb_cc));
+        assert_eq!(color_value("#ab"), None);
+    }
+}
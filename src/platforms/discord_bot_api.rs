@@ -0,0 +1,39 @@
+use crate::database;
+use crate::http::HttpResult;
+use crate::users;
+use chrono::{DateTime, Utc};
+use rand::{distr::Alphanumeric, Rng};
+use rocket::{response, serde::json::Json, State};
+use serde::Serialize;
+use sqlx::PgPool;
+
+const LINK_CODE_LENGTH: usize = 10;
+const LINK_CODE_VALID_MINUTES: i32 = 15;
+
+#[derive(Serialize)]
+pub struct DiscordLinkCode {
+    pub code: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A code, which the user sends to the discord bot via `/link <code>` to link their discord account.
+#[post("/api/user/platform/discord/link_code")]
+pub async fn post_api_user_platform_discord_link_code(
+    db_pool: &State<PgPool>,
+    jwt: HttpResult<users::Jwt>,
+) -> HttpResult<Json<DiscordLinkCode>> {
+    let user_id = jwt?.user_id()?;
+
+    let code: String = rand::rng()
+        .sample_iter(Alphanumeric)
+        .take(LINK_CODE_LENGTH)
+        .map(char::from)
+        .collect();
+
+    let expires_at =
+        database::set_discord_link_code(db_pool, &user_id, &code, LINK_CODE_VALID_MINUTES)
+            .await
+            .map_err(response::Debug)?;
+
+    Ok(Json(DiscordLinkCode { code, expires_at }))
+}
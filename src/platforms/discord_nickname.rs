@@ -0,0 +1,282 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serenity::all::{DiscordJsonError, EditMember, GuildId, Http, HttpError, UserId};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::num::NonZeroU64;
+use std::sync::Arc;
+
+use crate::{database, plurality, record_if_error, updater, users};
+
+pub const DISCORD_NICKNAME_MAX_LENGTH: usize = 32;
+
+// https://discord.com/developers/docs/topics/opcodes-and-status-codes#json-json-error-codes
+const DISCORD_ERROR_UNKNOWN_GUILD: isize = 10004;
+const DISCORD_ERROR_UNKNOWN_MEMBER: isize = 10007;
+const DISCORD_ERROR_MISSING_ACCESS: isize = 50001;
+const DISCORD_ERROR_MISSING_PERMISSIONS: isize = 50013;
+
+/// A server, in which the nickname is set, as configured by the user.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DiscordNicknameGuild {
+    pub guild_id: String,
+    pub template: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct DiscordNicknameTemplate {
+    pub guild_id: GuildId,
+    pub template: plurality::StatusTemplate,
+}
+
+pub fn parse_discord_nickname_guilds(
+    guilds: &[DiscordNicknameGuild],
+) -> Result<Vec<DiscordNicknameTemplate>> {
+    guilds
+        .iter()
+        .map(|g| {
+            let guild_id: NonZeroU64 = g
+                .guild_id
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("Invalid Discord server ID '{}'", g.guild_id))?;
+            Ok(DiscordNicknameTemplate {
+                guild_id: guild_id.into(),
+                template: plurality::StatusTemplate::parse(&g.template)?,
+            })
+        })
+        .collect()
+}
+
+pub fn format_discord_nickname(
+    config: &users::UserConfigForUpdater,
+    template: &plurality::StatusTemplate,
+    fronts: &[plurality::Fronter],
+) -> String {
+    let fronting_format = plurality::FrontingFormat {
+        max_length: Some(DISCORD_NICKNAME_MAX_LENGTH),
+        length_measure: plurality::DISCORD_STATUS_LENGTH_MEASURE,
+        cleaning: plurality::CleanForPlatform::NoClean,
+        prefix: config.status_prefix.clone(),
+        status_if_no_fronters: config.status_no_fronts.clone(),
+        truncate_names_to_length_if_status_too_long: config.status_truncate_names_to,
+        // the text of the template, e.g. "| The Crew", is kept by truncating the names and then leaving out the last ones
+        templates: std::iter::once(template.clone())
+            .chain(
+                (1..=fronts.len().max(1))
+                    .rev()
+                    .map(|max_names| template.with_shortened_names(max_names)),
+            )
+            .collect(),
+    };
+
+    plurality::format_fronting_status(&fronting_format, fronts)
+}
+
+/// Sets the nickname of the linked discord account in the configured servers via the discord bot.
+pub struct DiscordNicknameUpdater {
+    pub last_operation_error: Option<updater::UpdaterError>,
+    pub last_pushed_status: Option<updater::PushedStatus>,
+    last_pushed_nicknames: HashMap<GuildId, updater::PushedStatus>,
+    // None, if no discord bot is configured for this deployment
+    http: Option<Arc<Http>>,
+    // None when running without database, e.g. in local mode
+    db_pool: Option<PgPool>,
+}
+
+impl DiscordNicknameUpdater {
+    pub fn new(
+        _platform: updater::Platform,
+        discord_bot_token: Option<&str>,
+        db_pool: Option<PgPool>,
+    ) -> Self {
+        Self {
+            last_operation_error: None,
+            last_pushed_status: None,
+            last_pushed_nicknames: HashMap::new(),
+            http: discord_bot_token.map(|token| Arc::new(Http::new(token))),
+            db_pool,
+        }
+    }
+
+    #[allow(clippy::unused_async)]
+    pub async fn setup(&self, _config: &users::UserConfigForUpdater) -> Result<()> {
+        Ok(())
+    }
+
+    pub async fn update_fronting_status(
+        &mut self,
+        config: &users::UserConfigForUpdater,
+        fronts: &[plurality::Fronter],
+    ) -> Result<()> {
+        record_if_error!(self, self.set_nicknames(config, fronts).await)
+    }
+
+    async fn set_nicknames(
+        &mut self,
+        config: &users::UserConfigForUpdater,
+        fronts: &[plurality::Fronter],
+    ) -> Result<()> {
+        let (Some(http), Some(db_pool)) = (&self.http, &self.db_pool) else {
+            return Err(anyhow!("The Discord bot is not available on this server."));
+        };
+
+        // looked up on each update, as the account can be (un)linked any time
+        let discord_user_id: NonZeroU64 =
+            database::get_discord_user_id_by_user_id(db_pool, &config.user_id)
+                .await?
+                .ok_or_else(|| {
+                    anyhow!("No Discord account linked. Link it via /link with the Discord bot.")
+                })?
+                .parse()?;
+
+        let mut errors = vec![];
+
+        for guild in &config.discord_nickname_guilds {
+            let nickname = format_discord_nickname(config, &guild.template, fronts);
+
+            if self
+                .last_pushed_nicknames
+                .get(&guild.guild_id)
+                .is_some_and(|s| s.is_up_to_date(&nickname))
+            {
+                eprintln!(
+                    "Discord nickname in {} unchanged. Skipping.",
+                    guild.guild_id
+                );
+                continue;
+            }
+
+            match set_nickname(http, guild.guild_id, discord_user_id.into(), &nickname).await {
+                Ok(()) => {
+                    let pushed = updater::PushedStatus::now(nickname);
+                    self.last_pushed_nicknames
+                        .insert(guild.guild_id, pushed.clone());
+                    self.last_pushed_status = Some(pushed);
+                }
+                Err(e) => errors.push(e.to_string()),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!(errors.join(" ")))
+        }
+    }
+}
+
+async fn set_nickname(
+    http: &Http,
+    guild_id: GuildId,
+    discord_user_id: UserId,
+    nickname: &str,
+) -> Result<()> {
+    eprintln!("Setting Discord nickname in {guild_id}: {nickname}");
+
+    guild_id
+        .edit_member(http, discord_user_id, EditMember::new().nickname(nickname))
+        .await
+        .map(|_| ())
+        .map_err(|e| match e {
+            serenity::Error::Http(HttpError::UnsuccessfulRequest(response)) => {
+                anyhow!(describe_nickname_error(guild_id, &response.error))
+            }
+            other => anyhow!(other),
+        })
+}
+
+/// Explains the user what to do about the error, e.g. to grant the bot the necessary permissions.
+fn describe_nickname_error(guild_id: GuildId, error: &DiscordJsonError) -> String {
+    match error.code {
+        DISCORD_ERROR_MISSING_PERMISSIONS => format!(
+            "Missing permissions to change your nickname in the Discord server {guild_id}. \
+            The bot needs the 'Manage Nicknames' permission and a role above your highest role. \
+            Bots can't change the nickname of the server owner."
+        ),
+        DISCORD_ERROR_UNKNOWN_GUILD | DISCORD_ERROR_MISSING_ACCESS => format!(
+            "The bot isn't a member of the Discord server {guild_id}. Please invite it to the server."
+        ),
+        DISCORD_ERROR_UNKNOWN_MEMBER => format!(
+            "Your linked Discord account isn't a member of the Discord server {guild_id}."
+        ),
+        _ => format!(
+            "Failed to change your nickname in the Discord server {guild_id}: {}",
+            error.message
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guild(guild_id: &str, template: &str) -> DiscordNicknameGuild {
+        DiscordNicknameGuild {
+            guild_id: guild_id.to_owned(),
+            template: template.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_nickname_guilds_need_valid_ids_and_templates() {
+        assert!(parse_discord_nickname_guilds(&[guild("1234", "{names} | The Crew")]).is_ok());
+        assert!(parse_discord_nickname_guilds(&[guild("0", "{names}")]).is_err());
+        assert!(parse_discord_nickname_guilds(&[guild("my-server", "{names}")]).is_err());
+        assert!(parse_discord_nickname_guilds(&[guild("1234", "{names")]).is_err());
+    }
+
+    #[test]
+    fn test_nickname_shortens_names_to_discord_limit() -> Result<()> {
+        let config = users::config_for_tests(&users::UserConfigDbEntries::default())?;
+        let template = plurality::StatusTemplate::parse(r#"{names|join:" & "} | The Crew"#)?;
+
+        assert_eq!(
+            format_discord_nickname(
+                &config,
+                &template,
+                &[plurality::Fronter::for_tests("Alice")]
+            ),
+            "Alice | The Crew"
+        );
+
+        let many = [
+            plurality::Fronter::for_tests("Alice"),
+            plurality::Fronter::for_tests("Bob"),
+            plurality::Fronter::for_tests("Carol"),
+            plurality::Fronter::for_tests("Dave"),
+        ];
+        let nickname = format_discord_nickname(&config, &template, &many);
+        assert_eq!(nickname, "Ali & Bob & Car & Dav | The Crew");
+        assert_eq!(nickname.encode_utf16().count(), DISCORD_NICKNAME_MAX_LENGTH);
+
+        let even_more = [
+            many.as_slice(),
+            &[
+                plurality::Fronter::for_tests("Erin"),
+                plurality::Fronter::for_tests("Frank"),
+            ],
+        ]
+        .concat();
+        assert_eq!(
+            format_discord_nickname(&config, &template, &even_more),
+            "Ali & Bob & Car & Dav | The Crew"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_permissions_are_explained() -> Result<()> {
+        let error: DiscordJsonError = serde_json::from_value(serde_json::json!({
+            "code": 50013, "message": "Missing Permissions",
+        }))?;
+
+        let description = describe_nickname_error(GuildId::new(1234), &error);
+
+        assert!(description.contains("'Manage Nicknames'"));
+        assert!(description.contains("1234"));
+
+        Ok(())
+    }
+}
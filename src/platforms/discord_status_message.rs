@@ -13,12 +13,14 @@ struct Status {
 }
 
 pub struct DiscordStatusMessageUpdater {
-    pub last_operation_error: Option<String>,
+    pub last_operation_error: Option<updater::UpdaterError>,
+    pub last_pushed_status: Option<updater::PushedStatus>,
 }
 impl DiscordStatusMessageUpdater {
     pub const fn new(_platform: updater::Platform) -> Self {
         Self {
             last_operation_error: None,
+            last_pushed_status: None,
         }
     }
 
@@ -32,27 +34,43 @@ impl DiscordStatusMessageUpdater {
         config: &users::UserConfigForUpdater,
         fronts: &[plurality::Fronter],
     ) -> Result<()> {
-        record_if_error!(self, update_to_discord(config, fronts).await)
+        let status_string = format_discord_status(config, fronts);
+
+        if self
+            .last_pushed_status
+            .as_ref()
+            .is_some_and(|s| s.is_up_to_date(&status_string))
+        {
+            eprintln!("Discord status unchanged. Skipping update.");
+            return Ok(());
+        }
+
+        record_if_error!(
+            self,
+            set_discord_status(config, status_string.clone()).await
+        )?;
+
+        self.last_pushed_status = Some(updater::PushedStatus::now(status_string));
+
+        Ok(())
     }
 }
 
-async fn update_to_discord(
+pub fn format_discord_status(
     config: &users::UserConfigForUpdater,
     fronts: &[plurality::Fronter],
-) -> Result<()> {
+) -> String {
     let fronting_format = plurality::FrontingFormat {
         max_length: Some(plurality::DISCORD_STATUS_MAX_LENGTH),
+        length_measure: plurality::DISCORD_STATUS_LENGTH_MEASURE,
         cleaning: plurality::CleanForPlatform::NoClean,
         prefix: config.status_prefix.clone(),
         status_if_no_fronters: config.status_no_fronts.clone(),
         truncate_names_to_length_if_status_too_long: config.status_truncate_names_to,
+        templates: config.status_templates_discord_status_message.clone(),
     };
 
-    let status_string = plurality::format_fronting_status(&fronting_format, fronts);
-
-    set_discord_status(config, status_string).await?;
-
-    Ok(())
+    plurality::format_fronting_status(&fronting_format, fronts)
 }
 
 async fn set_discord_status(
@@ -72,18 +90,21 @@ async fn set_discord_status(
         },
     };
 
-    let result: User = config
+    let response = config
         .client
         .patch(discord_status_url)
         .header("Authorization", &config.discord_status_message_token.secret)
         .header("Content-Type", "application/json")
         .body(serde_json::to_string(&body)?)
         .send()
-        .await?
-        .error_for_status()?
-        .json()
         .await?;
 
+    if let Some(rate_limited) = updater::RateLimited::from_response(&response) {
+        return Err(rate_limited.into());
+    }
+
+    let result: User = response.error_for_status()?.json().await?;
+
     eprintln!("Changed Discord User: {result:?}");
 
     Ok(())
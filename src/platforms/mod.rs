@@ -1,10 +1,29 @@
+mod discord_bot;
+pub mod discord_bot_api;
+mod discord_nickname;
 mod discord_status_message;
 mod updater_macro;
 mod vrchat;
 pub mod vrchat_api;
 mod vrchat_auth;
+mod vrchat_auth_pending;
 mod vrchat_auth_types;
+mod vrchat_avatar;
+mod vrchat_osc;
+mod vrchat_profile;
 pub mod webview_api;
+mod webview_avatars;
+mod webview_formats;
+mod webview_theme;
 
+pub use discord_bot::*;
+pub use discord_nickname::*;
 pub use discord_status_message::*;
 pub use vrchat::*;
+pub use vrchat_auth::{VRChatNeedsTwoFactorAuth, VRChatSessionExpired};
+pub use vrchat_auth_pending::*;
+pub use vrchat_avatar::*;
+pub use vrchat_osc::*;
+pub use vrchat_profile::*;
+pub use webview_avatars::AvatarHttpClient;
+pub use webview_theme::*;
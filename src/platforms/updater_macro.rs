@@ -2,7 +2,11 @@
 macro_rules! record_if_error {
     ($self:expr, $result:expr) => {{
         let operation = $result;
-        $self.last_operation_error = operation.as_ref().err().map(|e| e.to_string());
+        let error = operation
+            .as_ref()
+            .err()
+            .map(|e| $crate::updater::UpdaterError::record($self.last_operation_error.take(), e));
+        $self.last_operation_error = error;
         operation
     }};
 }
@@ -1,33 +1,84 @@
+use crate::config_file;
+use crate::database;
+use crate::platforms::vrchat_auth_types::VRChatCredentialsWithCookie;
 use crate::plurality;
 use crate::record_if_error;
 use crate::updater;
-use crate::{platforms::vrchat_auth, users};
+use crate::{platforms::vrchat_auth, platforms::vrchat_avatar, platforms::vrchat_profile, users};
 use anyhow::anyhow;
 use anyhow::{Ok, Result};
+use sqlx::PgPool;
+use std::path::PathBuf;
 use vrchatapi::{
-    apis::{configuration::Configuration as VrcConfig, users_api},
+    apis::{avatars_api, configuration::Configuration as VrcConfig, users_api, Error as VrcError},
     models as vrc,
 };
 
 type InitializedUpdater = (VrcConfig, String);
+
+// where a renewed cookie is stored, such that the login survives restarts
+enum CookieStorage {
+    Database {
+        db_pool: PgPool,
+        application_user_secrets: database::ApplicationUserSecrets,
+    },
+    // in local mode
+    ConfigFile(PathBuf),
+}
+
 pub struct VRChatUpdater {
-    pub last_operation_error: Option<String>,
+    pub last_operation_error: Option<updater::UpdaterError>,
+    pub last_pushed_status: Option<updater::PushedStatus>,
+    // the login expired and only the user can login again with a 2FA code
+    needs_two_factor_auth: bool,
     initialized: Option<InitializedUpdater>,
+    cookie_storage: CookieStorage,
+    last_pushed_profile: Option<vrchat_profile::VRChatProfile>,
+    avatar_switcher: vrchat_avatar::VRChatAvatarSwitcher,
 }
 impl VRChatUpdater {
-    pub const fn new(_platform: updater::Platform) -> Self {
+    pub fn new(_platform: updater::Platform, shared_updaters: &updater::UpdaterManager) -> Self {
+        let cookie_storage = shared_updaters.db_pool.clone().map_or_else(
+            || CookieStorage::ConfigFile(config_file::config_file_path()),
+            |db_pool| CookieStorage::Database {
+                db_pool,
+                application_user_secrets: shared_updaters.application_user_secrets.clone(),
+            },
+        );
+
         Self {
             last_operation_error: None,
+            last_pushed_status: None,
+            needs_two_factor_auth: false,
             initialized: None,
+            cookie_storage,
+            last_pushed_profile: None,
+            avatar_switcher: vrchat_avatar::VRChatAvatarSwitcher::default(),
         }
     }
 
     pub async fn setup(&mut self, config: &users::UserConfigForUpdater) -> Result<()> {
-        let init_value = record_if_error!(
-            self,
-            vrchat_auth::authenticate_vrchat_with_cookie(config).await
-        );
-        self.initialized = Some(init_value?);
+        record_if_error!(self, self.authenticate(config).await)
+    }
+
+    async fn authenticate(&mut self, config: &users::UserConfigForUpdater) -> Result<()> {
+        let session = vrchat_auth::authenticate_vrchat_with_cookie(config).await;
+
+        self.needs_two_factor_auth = session
+            .as_ref()
+            .is_err_and(anyhow::Error::is::<vrchat_auth::VRChatNeedsTwoFactorAuth>);
+
+        let session = session?;
+        self.initialized = Some((session.vrchat_config, session.user_id));
+
+        // the session works regardless. without storing, the cookie is only renewed again after restarts.
+        if let Some(cookie) = session.renewed_cookie {
+            self.cookie_storage
+                .store(config, cookie)
+                .await
+                .map_err(|e| anyhow!("Failed to store the renewed VRChat cookie: {e}"))?;
+        }
+
         Ok(())
     }
 
@@ -36,48 +87,216 @@ impl VRChatUpdater {
         config: &users::UserConfigForUpdater,
         fronts: &[plurality::Fronter],
     ) -> Result<()> {
-        let initialized_updater = record_if_error!(
-            self,
-            self.initialized
-                .as_ref()
-                .ok_or_else(|| anyhow!("Updater not initalized!"))
-        );
-        record_if_error!(
-            self,
-            update_to_vrchat(config, initialized_updater?, fronts).await
-        )
+        record_if_error!(self, self.update(config, fronts).await)
+    }
+
+    async fn update(
+        &mut self,
+        config: &users::UserConfigForUpdater,
+        fronts: &[plurality::Fronter],
+    ) -> Result<()> {
+        if self.initialized.is_none() {
+            // logging in again would only send another 2FA code to the user
+            if self.needs_two_factor_auth {
+                return Err(anyhow!(vrchat_auth::VRChatNeedsTwoFactorAuth));
+            }
+            // e.g. after the login expired during the last update
+            self.authenticate(config).await?;
+        }
+
+        let initialized_updater = self
+            .initialized
+            .as_ref()
+            .ok_or_else(|| anyhow!("Updater not initalized!"))?;
+
+        // the avatar is switched independently of whether the status changed
+        let results = [
+            update_status(
+                &mut self.last_pushed_status,
+                &mut self.last_pushed_profile,
+                initialized_updater,
+                config,
+                fronts,
+            )
+            .await,
+            self.avatar_switcher
+                .update(&initialized_updater.0, config, fronts)
+                .await,
+        ];
+
+        let session_expired = results
+            .iter()
+            .any(|r| r.as_ref().is_err_and(is_unauthorized));
+        if session_expired {
+            eprintln!("{}", vrchat_auth::VRChatSessionExpired);
+            self.initialized = None;
+        }
+
+        // the first error is kept as is, such that its kind can still be determined
+        let mut errors = results.into_iter().filter_map(Result::err);
+        let Some(first) = errors.next() else {
+            return Ok(());
+        };
+        // unlike a rejected login, an expired session is renewed by the next update
+        let first = if session_expired {
+            first.context(vrchat_auth::VRChatSessionExpired)
+        } else {
+            first
+        };
+        let others: Vec<String> = errors.map(|e| e.to_string()).collect();
+
+        if others.is_empty() {
+            Err(first)
+        } else {
+            let message = format!("{first} {}", others.join(" "));
+            Err(first.context(message))
+        }
     }
 }
 
-async fn update_to_vrchat(
-    config: &users::UserConfigForUpdater,
+impl CookieStorage {
+    async fn store(&self, config: &users::UserConfigForUpdater, cookie: String) -> Result<()> {
+        match self {
+            Self::Database {
+                db_pool,
+                application_user_secrets,
+            } => {
+                let creds = VRChatCredentialsWithCookie::from_config(config);
+                vrchat_auth::store_vrchat_credentials(
+                    db_pool,
+                    application_user_secrets,
+                    &config.user_id,
+                    VRChatCredentialsWithCookie { cookie, ..creds },
+                )
+                .await
+            }
+            Self::ConfigFile(path) => config_file::write_vrchat_cookie(path, &cookie),
+        }
+    }
+}
+
+/// The HTTP status of a failed API request.
+pub fn vrchat_response_status(error: &anyhow::Error) -> Option<reqwest::StatusCode> {
+    const fn status<T>(error: &VrcError<T>) -> Option<reqwest::StatusCode> {
+        match error {
+            VrcError::ResponseError(response) => Some(response.status),
+            _ => None,
+        }
+    }
+
+    error
+        .downcast_ref::<VrcError<users_api::UpdateUserError>>()
+        .and_then(status)
+        .or_else(|| {
+            error
+                .downcast_ref::<VrcError<users_api::GetUserError>>()
+                .and_then(status)
+        })
+        .or_else(|| {
+            error
+                .downcast_ref::<VrcError<avatars_api::SelectAvatarError>>()
+                .and_then(status)
+        })
+}
+
+fn is_unauthorized(error: &anyhow::Error) -> bool {
+    vrchat_response_status(error) == Some(reqwest::StatusCode::UNAUTHORIZED)
+}
+
+async fn update_status(
+    last_pushed_status: &mut Option<updater::PushedStatus>,
+    last_pushed_profile: &mut Option<vrchat_profile::VRChatProfile>,
     initialized_updater: &InitializedUpdater,
+    config: &users::UserConfigForUpdater,
     fronts: &[plurality::Fronter],
 ) -> Result<()> {
+    let status_string = format_vrchat_status(config, fronts);
+    let profile = vrchat_profile::VRChatProfile::for_fronts(config, fronts);
+
+    if last_pushed_status
+        .as_ref()
+        .is_some_and(|s| s.is_up_to_date(&status_string))
+        && last_pushed_profile.as_ref() == Some(&profile)
+    {
+        eprintln!("VRChat status unchanged. Skipping update.");
+        return Ok(());
+    }
+
+    set_vrchat_status(initialized_updater, status_string.as_str(), &profile).await?;
+
+    *last_pushed_status = Some(updater::PushedStatus::now(status_string));
+    *last_pushed_profile = Some(profile);
+
+    Ok(())
+}
+
+pub fn format_vrchat_status(
+    config: &users::UserConfigForUpdater,
+    fronts: &[plurality::Fronter],
+) -> String {
     let fronting_format = plurality::FrontingFormat {
         max_length: Some(plurality::VRCHAT_MAX_ALLOWED_STATUS_LENGTH),
+        length_measure: plurality::VRCHAT_STATUS_LENGTH_MEASURE,
         cleaning: plurality::CleanForPlatform::VRChat,
         prefix: config.status_prefix.clone(),
         status_if_no_fronters: config.status_no_fronts.clone(),
         truncate_names_to_length_if_status_too_long: config.status_truncate_names_to,
+        templates: config.status_templates_vrchat.clone(),
     };
 
-    let status_string = plurality::format_fronting_status(&fronting_format, fronts);
-
-    set_vrchat_status(initialized_updater, status_string.as_str()).await
+    plurality::format_fronting_status(&fronting_format, fronts)
 }
 
 async fn set_vrchat_status(
     initialized_updater: &InitializedUpdater,
     status_string: &str,
+    profile: &vrchat_profile::VRChatProfile,
 ) -> Result<()> {
+    let (vrchat_config, user_id) = initialized_updater;
+
     let mut update_request = vrc::UpdateUserRequest::new();
     update_request.status_description = Some(status_string.to_string());
+    update_request.status = profile.state.map(Into::into);
+
+    if let Some(section) = &profile.bio_section {
+        // the rest of the bio may have been changed by the user in the meantime
+        let current_bio = users_api::get_user(vrchat_config, user_id).await?.bio;
+        let new_bio = vrchat_profile::splice_vrchat_bio(&current_bio, section)?;
+        if new_bio != current_bio {
+            update_request.bio = Some(new_bio);
+        }
+    }
 
-    let (vrchat_config, user_id) = initialized_updater;
     users_api::update_user(vrchat_config, user_id, Some(update_request))
         .await
         .inspect(|_| eprintln!("VRChat status updated successfully to: '{status_string}'"))?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vrchatapi::apis::ResponseContent;
+
+    fn response_error(status: reqwest::StatusCode) -> anyhow::Error {
+        anyhow!(VrcError::<users_api::UpdateUserError>::ResponseError(
+            ResponseContent {
+                status,
+                content: String::new(),
+                entity: None,
+            }
+        ))
+    }
+
+    #[test]
+    fn test_only_unauthorized_responses_trigger_a_new_login() {
+        assert!(is_unauthorized(&response_error(
+            reqwest::StatusCode::UNAUTHORIZED
+        )));
+        assert!(!is_unauthorized(&response_error(
+            reqwest::StatusCode::TOO_MANY_REQUESTS
+        )));
+        assert!(!is_unauthorized(&anyhow!("Unauthorized")));
+    }
+}
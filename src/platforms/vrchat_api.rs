@@ -1,36 +1,90 @@
+use crate::database;
 use crate::http::HttpResult;
-use crate::platforms::vrchat_auth_types::{
-    TwoFactorAuthMethod, VRChatCredentialsWithCookie, VRChatCredentialsWithTwoFactorAuth,
-};
+use crate::platforms::vrchat_auth_pending::VRChatPendingAuths;
+use crate::platforms::vrchat_auth_types::{TwoFactorAuthResolveRequest, VRChatAuthOutcome};
 use crate::platforms::{vrchat_auth, vrchat_auth_types};
+use crate::updater;
 use crate::users;
+use anyhow::anyhow;
 use either::Either;
-use rocket::serde::json::Json;
+use rocket::{serde::json::Json, State};
+use sqlx::PgPool;
 
 #[post("/api/user/platform/vrchat/auth_2fa/request", data = "<creds>")]
 pub async fn post_api_user_platform_vrchat_auth_2fa_request(
     creds: Json<vrchat_auth_types::VRChatCredentials>,
-    _jwt: HttpResult<users::Jwt>, // request should be authenticated, but we don't need user id
-) -> HttpResult<Json<Either<VRChatCredentialsWithCookie, TwoFactorAuthMethod>>> {
+    jwt: HttpResult<users::Jwt>,
+    db_pool: &State<PgPool>,
+    app_user_secrets: &State<database::ApplicationUserSecrets>,
+    pending_auths: &State<VRChatPendingAuths>,
+    client: &State<reqwest::Client>,
+    shared_updaters: &State<updater::UpdaterManager>,
+) -> HttpResult<Json<VRChatAuthOutcome>> {
+    let user_id = jwt?.user_id()?;
     let creds = creds.into_inner();
 
-    let creds_or_tfa_method = vrchat_auth::authenticate_vrchat_for_new_cookie(creds).await?;
-
-    Ok(Json(creds_or_tfa_method))
+    match vrchat_auth::authenticate_vrchat_for_new_cookie(creds).await? {
+        Either::Left(creds_with_cookie) => {
+            vrchat_auth::store_vrchat_credentials(
+                db_pool,
+                app_user_secrets,
+                &user_id,
+                creds_with_cookie,
+            )
+            .await?;
+            pending_auths.remove(&user_id)?;
+            // the running updater still uses the old login
+            updater::api::restart_updater_for_user(
+                &user_id,
+                db_pool,
+                app_user_secrets,
+                client,
+                shared_updaters,
+            )
+            .await?;
+            Ok(Json(VRChatAuthOutcome::Authenticated))
+        }
+        Either::Right(pending) => {
+            let method = pending.method.clone();
+            pending_auths.insert(user_id, pending)?;
+            Ok(Json(VRChatAuthOutcome::TwoFactorAuthRequired(method)))
+        }
+    }
 }
 
-#[post(
-    "/api/user/platform/vrchat/auth_2fa/resolve",
-    data = "<creds_with_tfa>"
-)]
+#[post("/api/user/platform/vrchat/auth_2fa/resolve", data = "<resolve>")]
 pub async fn post_api_user_platform_vrchat_auth_2fa_resolve(
-    _jwt: HttpResult<users::Jwt>, // request should be authenticated, but we don't need user id
-    creds_with_tfa: Json<VRChatCredentialsWithTwoFactorAuth>,
-) -> HttpResult<Json<VRChatCredentialsWithCookie>> {
-    let creds_with_tfa = creds_with_tfa.into_inner();
+    jwt: HttpResult<users::Jwt>,
+    resolve: Json<TwoFactorAuthResolveRequest>,
+    db_pool: &State<PgPool>,
+    app_user_secrets: &State<database::ApplicationUserSecrets>,
+    pending_auths: &State<VRChatPendingAuths>,
+    client: &State<reqwest::Client>,
+    shared_updaters: &State<updater::UpdaterManager>,
+) -> HttpResult<Json<VRChatAuthOutcome>> {
+    let user_id = jwt?.user_id()?;
+    let resolve = resolve.into_inner();
+
+    let pending = pending_auths
+        .get(&user_id)?
+        .ok_or_else(|| anyhow!("No pending VRChat login. Please start the login again."))?;
+
+    // a wrong code keeps the pending login, such that the user can retry
+    let creds_with_cookie =
+        vrchat_auth::authenticate_vrchat_for_new_cookie_with_2fa(&pending, resolve.code).await?;
 
-    let valid_creds =
-        vrchat_auth::authenticate_vrchat_for_new_cookie_with_2fa(creds_with_tfa).await?;
+    vrchat_auth::store_vrchat_credentials(db_pool, app_user_secrets, &user_id, creds_with_cookie)
+        .await?;
+    pending_auths.remove(&user_id)?;
+    // the running updater still uses the old login
+    updater::api::restart_updater_for_user(
+        &user_id,
+        db_pool,
+        app_user_secrets,
+        client,
+        shared_updaters,
+    )
+    .await?;
 
-    Ok(Json(valid_creds))
+    Ok(Json(VRChatAuthOutcome::Authenticated))
 }
@@ -1,6 +1,6 @@
+use crate::database;
 use crate::platforms::vrchat_auth_types::{
     TwoFactorAuthCode, TwoFactorAuthMethod, VRChatCredentials, VRChatCredentialsWithCookie,
-    VRChatCredentialsWithTwoFactorAuth,
 };
 use crate::users;
 
@@ -8,6 +8,7 @@ use anyhow::{anyhow, Result};
 use either::Either;
 use reqwest::cookie::{self, CookieStore};
 use reqwest::Url;
+use sqlx::PgPool;
 use std::str::FromStr;
 use std::sync::Arc;
 use vrchatapi::{
@@ -25,31 +26,100 @@ const VRCHAT_UPDATER_USER_AGENT: &str = concat!(
 
 const VRCHAT_COOKIE_URL: &str = "https://api.vrchat.cloud";
 
-/* Called in updater. Cookie is only validated, no new cookie is created. */
+/// The login needs a 2FA code, which only the user can provide via the 2FA flow.
+#[derive(Debug)]
+pub struct VRChatNeedsTwoFactorAuth;
+
+impl std::fmt::Display for VRChatNeedsTwoFactorAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "VRChat login expired and needs a 2FA code. Please login to VRChat again."
+        )
+    }
+}
+
+impl std::error::Error for VRChatNeedsTwoFactorAuth {}
+
+/// The session cookie was rejected during an update. The next update logs in again with the stored login.
+#[derive(Debug)]
+pub struct VRChatSessionExpired;
+
+impl std::fmt::Display for VRChatSessionExpired {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "VRChat login expired. Logging in again on the next update."
+        )
+    }
+}
+
+impl std::error::Error for VRChatSessionExpired {}
+
+pub struct VRChatSession {
+    pub vrchat_config: VrcConfig,
+    pub user_id: String,
+    // Some, if the stored cookie had expired and a new one was obtained by logging in again
+    pub renewed_cookie: Option<String>,
+}
+
+/* Called in updater. When the cookie has expired, the stored username and password are
+used to login again. This only works without 2FA, otherwise `VRChatNeedsTwoFactorAuth` is returned. */
 pub async fn authenticate_vrchat_with_cookie(
     config: &users::UserConfigForUpdater,
-) -> Result<(VrcConfig, String)> {
+) -> Result<VRChatSession> {
     let creds = VRChatCredentialsWithCookie::from_config(config);
 
-    let (vrchat_config, _) =
+    let (vrchat_config, cookie_store) =
         new_vrchat_config_with_basic_auth_and_optional_cookie(Either::Right(&creds))?;
 
-    let () = match authentication_api::get_current_user(&vrchat_config).await? {
-        vrc::EitherUserOrTwoFactor::CurrentUser(_me) => {
-            eprintln!("VRChat Cookie valid for {}", config.user_id);
-            Ok(())
+    // an expired cookie is ignored by VRChat and the basic auth is used to login instead
+    match authentication_api::get_current_user(&vrchat_config).await? {
+        vrc::EitherUserOrTwoFactor::CurrentUser(me) => {
+            let cookie = extract_new_cookie(&cookie_store)?;
+            let renewed_cookie = (cookie != creds.cookie).then_some(cookie);
+            if renewed_cookie.is_some() {
+                eprintln!("VRChat Cookie renewed for {}", config.user_id);
+            } else {
+                eprintln!("VRChat Cookie valid for {}", config.user_id);
+            }
+
+            Ok(VRChatSession {
+                vrchat_config,
+                user_id: me.id,
+                renewed_cookie,
+            })
         }
-        vrc::EitherUserOrTwoFactor::RequiresTwoFactorAuth(_) => Err(anyhow!("Login failed")),
-    }?;
+        vrc::EitherUserOrTwoFactor::RequiresTwoFactorAuth(_) => {
+            Err(anyhow!(VRChatNeedsTwoFactorAuth))
+        }
+    }
+}
 
-    let user_id = get_vrchat_user_id(config, &vrchat_config).await?;
+/// A login, which waits for the 2FA code of the user. The cookie jar holds the partial login.
+#[derive(Clone)]
+pub struct PendingVRChatAuth {
+    pub creds: VRChatCredentials,
+    pub method: TwoFactorAuthMethod,
+    vrchat_config: VrcConfig,
+    cookie_store: Arc<cookie::Jar>,
+}
 
-    Ok((vrchat_config, user_id))
+#[cfg(test)]
+impl PendingVRChatAuth {
+    pub fn new_for_test(creds: VRChatCredentials, method: TwoFactorAuthMethod) -> Self {
+        Self {
+            creds,
+            method,
+            vrchat_config: VrcConfig::default(),
+            cookie_store: Arc::new(cookie::Jar::default()),
+        }
+    }
 }
 
 pub async fn authenticate_vrchat_for_new_cookie(
     creds: VRChatCredentials,
-) -> Result<Either<VRChatCredentialsWithCookie, TwoFactorAuthMethod>> {
+) -> Result<Either<VRChatCredentialsWithCookie, PendingVRChatAuth>> {
     let (vrchat_config, cookie_store) =
         new_vrchat_config_with_basic_auth_and_optional_cookie(Either::Left(&creds))?;
 
@@ -62,28 +132,49 @@ pub async fn authenticate_vrchat_for_new_cookie(
         }
 
         vrc::EitherUserOrTwoFactor::RequiresTwoFactorAuth(requires_auth) => {
-            let auth_method = TwoFactorAuthMethod::from(&requires_auth);
-            Ok(Either::Right(auth_method))
+            let method = TwoFactorAuthMethod::from(&requires_auth);
+            Ok(Either::Right(PendingVRChatAuth {
+                creds,
+                method,
+                vrchat_config,
+                cookie_store,
+            }))
         }
     }
 }
 
 pub async fn authenticate_vrchat_for_new_cookie_with_2fa(
-    creds_with_tfa: VRChatCredentialsWithTwoFactorAuth,
+    pending: &PendingVRChatAuth,
+    code: TwoFactorAuthCode,
 ) -> Result<VRChatCredentialsWithCookie> {
-    let (vrchat_config, cookie_store) =
-        new_vrchat_config_with_basic_auth_and_optional_cookie(Either::Left(&creds_with_tfa.creds))?;
-
-    let () = vrchat_verify_2fa(creds_with_tfa.method, creds_with_tfa.code, &vrchat_config).await?;
+    let () = vrchat_verify_2fa(pending.method.clone(), code, &pending.vrchat_config).await?;
 
-    let cookie = extract_new_cookie(&cookie_store)?;
+    let cookie = extract_new_cookie(&pending.cookie_store)?;
 
     Ok(VRChatCredentialsWithCookie::from(
-        &creds_with_tfa.creds,
+        &pending.creds,
         cookie.as_str(),
     ))
 }
 
+/// Stores the login in the encrypted config of the user.
+pub async fn store_vrchat_credentials(
+    db_pool: &PgPool,
+    application_user_secrets: &database::ApplicationUserSecrets,
+    user_id: &users::UserId,
+    creds: VRChatCredentialsWithCookie,
+) -> Result<()> {
+    database::set_vrchat_credentials(
+        db_pool,
+        user_id,
+        &creds.creds.username,
+        &creds.creds.password,
+        &creds.cookie,
+        application_user_secrets,
+    )
+    .await
+}
+
 async fn vrchat_verify_2fa(
     method: TwoFactorAuthMethod,
     auth_code: TwoFactorAuthCode,
@@ -142,16 +233,3 @@ fn extract_new_cookie(cookie_store: &Arc<cookie::Jar>) -> Result<String> {
         .ok_or_else(|| anyhow!("no cookies"))?;
     Ok(cookie_value.to_str()?.to_owned())
 }
-
-async fn get_vrchat_user_id(
-    config: &users::UserConfigForUpdater,
-    vrchat_config: &VrcConfig,
-) -> Result<String> {
-    match authentication_api::get_current_user(vrchat_config).await? {
-        vrc::EitherUserOrTwoFactor::CurrentUser(user) => Ok(user.id),
-        vrc::EitherUserOrTwoFactor::RequiresTwoFactorAuth(_) => Err(anyhow!(
-            "Cookie invalid for user {}",
-            config.vrchat_username.secret
-        )),
-    }
-}
@@ -0,0 +1,108 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::platforms::vrchat_auth::PendingVRChatAuth;
+use crate::users::UserId;
+
+// 2FA codes are only valid for a few minutes anyways
+const PENDING_AUTH_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Logins waiting for the 2FA code, one per user. They're kept on the server,
+/// such that neither the partial login nor the resulting cookie reach the browser.
+#[derive(Clone, Default)]
+pub struct VRChatPendingAuths {
+    pending: Arc<Mutex<HashMap<UserId, (PendingVRChatAuth, Instant)>>>,
+}
+
+impl VRChatPendingAuths {
+    /// Replaces a previous pending login of the user.
+    pub fn insert(&self, user_id: UserId, auth: PendingVRChatAuth) -> Result<()> {
+        self.insert_at(user_id, auth, Instant::now())
+    }
+
+    pub fn get(&self, user_id: &UserId) -> Result<Option<PendingVRChatAuth>> {
+        self.get_at(user_id, Instant::now())
+    }
+
+    pub fn remove(&self, user_id: &UserId) -> Result<()> {
+        self.lock()?.remove(user_id);
+        Ok(())
+    }
+
+    fn insert_at(&self, user_id: UserId, auth: PendingVRChatAuth, now: Instant) -> Result<()> {
+        let mut pending = self.lock()?;
+        // abandoned logins are cleaned up here, so that they don't pile up
+        pending.retain(|_, (_, expires_at)| *expires_at > now);
+        pending.insert(user_id, (auth, now + PENDING_AUTH_TTL));
+        drop(pending);
+        Ok(())
+    }
+
+    fn get_at(&self, user_id: &UserId, now: Instant) -> Result<Option<PendingVRChatAuth>> {
+        Ok(self
+            .lock()?
+            .get(user_id)
+            .filter(|(_, expires_at)| *expires_at > now)
+            .map(|(auth, _)| auth.clone()))
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, HashMap<UserId, (PendingVRChatAuth, Instant)>>> {
+        self.pending.lock().map_err(|e| anyhow!(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platforms::vrchat_auth_types::{TwoFactorAuthMethod, VRChatCredentials};
+
+    fn pending_auth(username: &str) -> PendingVRChatAuth {
+        PendingVRChatAuth::new_for_test(
+            VRChatCredentials {
+                username: username.to_owned(),
+                password: "password".to_owned(),
+            },
+            TwoFactorAuthMethod::TwoFactorAuthMethodEmail,
+        )
+    }
+
+    fn user(id: u128) -> UserId {
+        UserId::from(sqlx::types::Uuid::from_u128(id))
+    }
+
+    #[test]
+    fn test_pending_auths_are_per_user_and_expire() -> Result<()> {
+        let auths = VRChatPendingAuths::default();
+        let now = Instant::now();
+
+        auths.insert_at(user(1), pending_auth("alice"), now)?;
+
+        let alice = auths.get_at(&user(1), now + Duration::from_secs(60))?;
+        assert_eq!(alice.map(|a| a.creds.username), Some("alice".to_owned()));
+        assert!(auths.get_at(&user(2), now)?.is_none());
+        assert!(auths.get_at(&user(1), now + PENDING_AUTH_TTL)?.is_none());
+
+        auths.remove(&user(1))?;
+        assert!(auths.get_at(&user(1), now)?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_login_replaces_pending_one_and_expired_ones_are_cleaned_up() -> Result<()> {
+        let auths = VRChatPendingAuths::default();
+        let now = Instant::now();
+
+        auths.insert_at(user(1), pending_auth("alice"), now)?;
+        auths.insert_at(user(2), pending_auth("bob"), now)?;
+        auths.insert_at(user(1), pending_auth("alice2"), now + PENDING_AUTH_TTL)?;
+
+        let alice = auths.get_at(&user(1), now + PENDING_AUTH_TTL)?;
+        assert_eq!(alice.map(|a| a.creds.username), Some("alice2".to_owned()));
+        assert_eq!(auths.lock()?.len(), 1);
+
+        Ok(())
+    }
+}
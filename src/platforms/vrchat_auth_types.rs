@@ -69,9 +69,14 @@ impl From<TwoFactorAuthCode> for String {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug)]
-pub struct VRChatCredentialsWithTwoFactorAuth {
-    pub creds: VRChatCredentials,
-    pub method: TwoFactorAuthMethod,
+#[derive(Clone, Deserialize, Debug)]
+pub struct TwoFactorAuthResolveRequest {
     pub code: TwoFactorAuthCode,
 }
+
+/// The result of a login. The cookie is stored on the server and never sent to the browser.
+#[derive(Clone, Serialize, Debug)]
+pub enum VRChatAuthOutcome {
+    Authenticated,
+    TwoFactorAuthRequired(TwoFactorAuthMethod),
+}
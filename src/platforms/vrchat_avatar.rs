@@ -0,0 +1,326 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::time::Instant;
+use vrchatapi::apis::{avatars_api, configuration::Configuration as VrcConfig, Error as VrcError};
+
+use crate::{plurality, sqlx_text_enum, users};
+
+// VRChat doesn't document the limit. Switching at most once a minute stays well below it.
+pub const VRCHAT_AVATAR_SWITCH_MIN_INTERVAL: Duration = Duration::from_secs(60);
+
+// when VRChat rate limited us nonetheless, wait considerably longer
+const VRCHAT_AVATAR_SWITCH_RATE_LIMITED_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+const VRCHAT_AVATAR_ID_PREFIX: &str = "avtr_";
+
+/// The avatar, which is selected while the fronter (member or custom front ID or name) is fronting.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct VRChatAvatar {
+    pub fronter: String,
+    pub avatar_id: String,
+}
+
+/// Which avatar to select, when multiple fronters with an avatar are fronting.
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    strum_macros::Display,
+    strum_macros::EnumString,
+    PartialEq,
+    Eq,
+)]
+pub enum VRChatAvatarCofrontPolicy {
+    // the first one in the fronting status
+    #[default]
+    First,
+    // the one, who has been fronting for the longest time
+    Longest,
+    // keep the current avatar until only one of them is fronting
+    KeepCurrent,
+}
+
+pub fn validate_vrchat_avatars(avatars: Vec<VRChatAvatar>) -> Result<Vec<VRChatAvatar>> {
+    for a in &avatars {
+        let id = a
+            .avatar_id
+            .strip_prefix(VRCHAT_AVATAR_ID_PREFIX)
+            .unwrap_or_default();
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(anyhow!(
+                "Invalid VRChat avatar ID '{}' for '{}'. It should look like '{VRCHAT_AVATAR_ID_PREFIX}...'",
+                a.avatar_id,
+                a.fronter
+            ));
+        }
+    }
+    Ok(avatars)
+}
+
+/// The avatar, which should be selected for the fronts. None, if the avatar should be left as it is.
+pub fn select_vrchat_avatar<'a>(
+    avatars: &'a [VRChatAvatar],
+    policy: VRChatAvatarCofrontPolicy,
+    fronts: &[plurality::Fronter],
+) -> Option<&'a str> {
+    let candidates: Vec<(&plurality::Fronter, &VRChatAvatar)> = fronts
+        .iter()
+        .filter_map(|f| {
+            let avatar = avatars
+                .iter()
+                .find(|a| a.fronter == f.id || a.fronter == f.name)?;
+            Some((f, avatar))
+        })
+        .collect();
+
+    let selected = match (candidates.as_slice(), policy) {
+        ([], _) | ([_, _, ..], VRChatAvatarCofrontPolicy::KeepCurrent) => None,
+        ([only], _) => Some(only),
+        (all, VRChatAvatarCofrontPolicy::First) => all.first(),
+        // fronters without a known start are considered to have started last
+        (all, VRChatAvatarCofrontPolicy::Longest) => all
+            .iter()
+            .min_by_key(|(f, _)| (f.start_time.is_none(), f.start_time)),
+    };
+
+    selected.map(|(_, a)| a.avatar_id.as_str())
+}
+
+/// Switches the avatar, when the selected avatar for the fronts changes.
+#[derive(Default)]
+pub struct VRChatAvatarSwitcher {
+    last_selected_avatar: Option<String>,
+    limiter: AvatarSwitchLimiter,
+}
+
+impl VRChatAvatarSwitcher {
+    pub async fn update(
+        &mut self,
+        vrchat_config: &VrcConfig,
+        config: &users::UserConfigForUpdater,
+        fronts: &[plurality::Fronter],
+    ) -> Result<()> {
+        let Some(avatar_id) = select_vrchat_avatar(
+            &config.vrchat_avatars,
+            config.vrchat_avatar_cofront_policy,
+            fronts,
+        ) else {
+            return Ok(());
+        };
+
+        if self.last_selected_avatar.as_deref() == Some(avatar_id) {
+            return Ok(());
+        }
+
+        if !self.limiter.is_allowed(Instant::now()) {
+            eprintln!("VRChat avatar switch to {avatar_id} postponed due to rate limits.");
+            return Ok(());
+        }
+
+        select_avatar(vrchat_config, avatar_id, &mut self.limiter).await?;
+        self.last_selected_avatar = Some(avatar_id.to_owned());
+
+        Ok(())
+    }
+}
+
+/// Limits how often the avatar is switched to respect the rate limits of the API.
+/// A switch which is due too early is postponed to a later update.
+#[derive(Default)]
+struct AvatarSwitchLimiter {
+    next_switch_allowed_at: Option<Instant>,
+}
+
+impl AvatarSwitchLimiter {
+    fn is_allowed(&self, now: Instant) -> bool {
+        self.next_switch_allowed_at.is_none_or(|at| now >= at)
+    }
+
+    fn record_attempt(&mut self, now: Instant, rate_limited: bool) {
+        let interval = if rate_limited {
+            VRCHAT_AVATAR_SWITCH_RATE_LIMITED_INTERVAL
+        } else {
+            VRCHAT_AVATAR_SWITCH_MIN_INTERVAL
+        };
+        self.next_switch_allowed_at = Some(now + interval);
+    }
+}
+
+// records the attempt in the limiter, also if it failed
+async fn select_avatar(
+    vrchat_config: &VrcConfig,
+    avatar_id: &str,
+    limiter: &mut AvatarSwitchLimiter,
+) -> Result<()> {
+    let result = avatars_api::select_avatar(vrchat_config, avatar_id).await;
+
+    let rate_limited = matches!(
+        &result,
+        Err(VrcError::ResponseError(response))
+            if response.status == reqwest::StatusCode::TOO_MANY_REQUESTS
+    );
+    limiter.record_attempt(Instant::now(), rate_limited);
+
+    result
+        .inspect(|_| eprintln!("VRChat avatar switched to {avatar_id}"))
+        .map(|_| ())
+        .map_err(|e| select_avatar_error(e, avatar_id))
+}
+
+// the response error is kept, such that its status still determines the kind of the error
+fn select_avatar_error(
+    error: VrcError<avatars_api::SelectAvatarError>,
+    avatar_id: &str,
+) -> anyhow::Error {
+    let message = match &error {
+        VrcError::ResponseError(response)
+            if response.status == reqwest::StatusCode::TOO_MANY_REQUESTS =>
+        {
+            "VRChat rate limited switching the avatar. Retrying later.".to_owned()
+        }
+        VrcError::ResponseError(response) if response.status == reqwest::StatusCode::NOT_FOUND => {
+            format!("VRChat avatar {avatar_id} not found or not available to you.")
+        }
+        _ => return anyhow!(error),
+    };
+
+    anyhow!(error).context(message)
+}
+
+sqlx_text_enum!(VRChatAvatarCofrontPolicy);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::updater;
+    use chrono::Utc;
+
+    fn hours_ago(hours: i64) -> chrono::DateTime<Utc> {
+        Utc::now() - chrono::Duration::hours(hours)
+    }
+
+    fn avatars() -> Vec<VRChatAvatar> {
+        vec![
+            VRChatAvatar {
+                fronter: "Alice".to_owned(),
+                avatar_id: "avtr_alice".to_owned(),
+            },
+            VRChatAvatar {
+                fronter: "bob".to_owned(),
+                avatar_id: "avtr_bob".to_owned(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_avatar_of_single_fronter_is_selected_by_name_or_id() {
+        let avatars = avatars();
+        let policy = VRChatAvatarCofrontPolicy::KeepCurrent;
+
+        let alice = [
+            plurality::Fronter::for_tests("Alice"),
+            plurality::Fronter::for_tests("Carol"),
+        ];
+        assert_eq!(
+            select_vrchat_avatar(&avatars, policy, &alice),
+            Some("avtr_alice")
+        );
+        assert_eq!(
+            select_vrchat_avatar(&avatars, policy, &[plurality::Fronter::for_tests("Bob")]),
+            Some("avtr_bob")
+        );
+        assert_eq!(
+            select_vrchat_avatar(&avatars, policy, &[plurality::Fronter::for_tests("Carol")]),
+            None
+        );
+        assert_eq!(select_vrchat_avatar(&avatars, policy, &[]), None);
+    }
+
+    #[test]
+    fn test_cofront_policy_decides_between_fronters_with_avatars() {
+        let avatars = avatars();
+        let fronts = [
+            plurality::Fronter::for_tests("Alice").with_start_time(hours_ago(1)),
+            plurality::Fronter::for_tests("Bob").with_start_time(hours_ago(3)),
+            plurality::Fronter::for_tests("Carol").with_start_time(hours_ago(5)),
+        ];
+
+        assert_eq!(
+            select_vrchat_avatar(&avatars, VRChatAvatarCofrontPolicy::First, &fronts),
+            Some("avtr_alice")
+        );
+        assert_eq!(
+            select_vrchat_avatar(&avatars, VRChatAvatarCofrontPolicy::Longest, &fronts),
+            Some("avtr_bob")
+        );
+        assert_eq!(
+            select_vrchat_avatar(&avatars, VRChatAvatarCofrontPolicy::KeepCurrent, &fronts),
+            None
+        );
+
+        let unknown_start = [
+            plurality::Fronter::for_tests("Alice").with_start_time(hours_ago(1)),
+            plurality::Fronter::for_tests("Bob"),
+        ];
+        assert_eq!(
+            select_vrchat_avatar(&avatars, VRChatAvatarCofrontPolicy::Longest, &unknown_start),
+            Some("avtr_alice")
+        );
+    }
+
+    #[test]
+    fn test_avatar_switches_are_rate_limited() {
+        let mut limiter = AvatarSwitchLimiter::default();
+        let now = Instant::now();
+        assert!(limiter.is_allowed(now));
+
+        limiter.record_attempt(now, false);
+        assert!(!limiter.is_allowed(now + Duration::from_secs(1)));
+        assert!(limiter.is_allowed(now + VRCHAT_AVATAR_SWITCH_MIN_INTERVAL));
+
+        limiter.record_attempt(now, true);
+        assert!(!limiter.is_allowed(now + VRCHAT_AVATAR_SWITCH_MIN_INTERVAL));
+        assert!(limiter.is_allowed(now + VRCHAT_AVATAR_SWITCH_RATE_LIMITED_INTERVAL));
+    }
+
+    #[test]
+    fn test_rate_limited_avatar_switches_are_retried() {
+        let error = select_avatar_error(
+            VrcError::ResponseError(vrchatapi::apis::ResponseContent {
+                status: reqwest::StatusCode::TOO_MANY_REQUESTS,
+                content: String::new(),
+                entity: None,
+            }),
+            "avtr_alice",
+        );
+
+        assert_eq!(
+            error.to_string(),
+            "VRChat rate limited switching the avatar. Retrying later."
+        );
+        assert_eq!(
+            updater::UpdaterErrorKind::of(&error),
+            updater::UpdaterErrorKind::RateLimited
+        );
+    }
+
+    #[test]
+    fn test_avatar_ids_are_validated() {
+        let avatar = |id: &str| VRChatAvatar {
+            fronter: "Alice".to_owned(),
+            avatar_id: id.to_owned(),
+        };
+
+        assert!(
+            validate_vrchat_avatars(vec![avatar("avtr_c38a1615-5bf5-42b4-84eb-a8b6c37cbd11")])
+                .is_ok()
+        );
+        assert!(validate_vrchat_avatars(vec![avatar("avtr_")]).is_err());
+        assert!(validate_vrchat_avatars(vec![avatar("c38a1615")]).is_err());
+        assert!(validate_vrchat_avatars(vec![avatar("avtr_../../users")]).is_err());
+    }
+}
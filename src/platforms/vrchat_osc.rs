@@ -0,0 +1,312 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::sync::watch;
+
+use crate::{plurality, record_if_error, updater, users};
+
+/// The chatbox shows at most this many characters.
+pub const VRCHAT_CHATBOX_MAX_LENGTH: usize = 144;
+
+// chatbox messages disappear after a while. resending keeps them visible.
+// VRChat rate limits the chatbox, so this shouldn't be much shorter.
+const CHATBOX_RESEND_INTERVAL: Duration = Duration::from_secs(10);
+
+const CHATBOX_INPUT_ADDRESS: &str = "/chatbox/input";
+const AVATAR_PARAMETERS_ADDRESS: &str = "/avatar/parameters/";
+
+// characters with special meaning in OSC address patterns
+const OSC_RESERVED_CHARACTERS: [char; 9] = [' ', '#', '*', ',', '?', '[', ']', '{', '}'];
+
+/// A bool avatar parameter, which is true while the fronter (member or custom front ID or name) is fronting.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct VRChatOscAvatarParameter {
+    pub fronter: String,
+    pub parameter: String,
+}
+
+pub fn validate_vrchat_osc_avatar_parameters(
+    parameters: Vec<VRChatOscAvatarParameter>,
+) -> Result<Vec<VRChatOscAvatarParameter>> {
+    for p in &parameters {
+        if p.parameter.is_empty() || p.parameter.contains(OSC_RESERVED_CHARACTERS) {
+            return Err(anyhow!(
+                "Invalid avatar parameter name '{}'. It may not be empty or contain any of {OSC_RESERVED_CHARACTERS:?}",
+                p.parameter
+            ));
+        }
+    }
+    Ok(parameters)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OscArgument {
+    String(String),
+    Bool(bool),
+}
+
+/// Encodes an OSC 1.0 message with the address and arguments.
+pub fn encode_osc_message(address: &str, arguments: &[OscArgument]) -> Vec<u8> {
+    let type_tags: String = std::iter::once(',')
+        .chain(arguments.iter().map(|a| match a {
+            OscArgument::String(_) => 's',
+            OscArgument::Bool(true) => 'T',
+            OscArgument::Bool(false) => 'F',
+        }))
+        .collect();
+
+    let mut message = vec![];
+    push_osc_string(&mut message, address);
+    push_osc_string(&mut message, &type_tags);
+
+    for argument in arguments {
+        match argument {
+            OscArgument::String(s) => push_osc_string(&mut message, s),
+            // bools are encoded in the type tag only
+            OscArgument::Bool(_) => {}
+        }
+    }
+
+    message
+}
+
+// null terminated and padded to a multiple of 4 bytes
+fn push_osc_string(message: &mut Vec<u8>, s: &str) {
+    message.extend_from_slice(s.as_bytes());
+    let padding = 4 - s.len() % 4;
+    message.extend(std::iter::repeat_n(0, padding));
+}
+
+/// Shows the text immediately in the chatbox without the notification sound.
+pub fn chatbox_message(text: &str) -> Vec<u8> {
+    encode_osc_message(
+        CHATBOX_INPUT_ADDRESS,
+        &[
+            OscArgument::String(text.to_owned()),
+            OscArgument::Bool(true),
+            OscArgument::Bool(false),
+        ],
+    )
+}
+
+pub fn avatar_parameter_message(parameter: &str, value: bool) -> Vec<u8> {
+    encode_osc_message(
+        &format!("{AVATAR_PARAMETERS_ADDRESS}{parameter}"),
+        &[OscArgument::Bool(value)],
+    )
+}
+
+pub fn format_vrchat_chatbox(
+    config: &users::UserConfigForUpdater,
+    fronts: &[plurality::Fronter],
+) -> String {
+    // unlike the status, the chatbox shows most unicode characters
+    let fronting_format = plurality::FrontingFormat {
+        max_length: Some(VRCHAT_CHATBOX_MAX_LENGTH),
+        length_measure: plurality::LengthMeasure::Chars,
+        cleaning: plurality::CleanForPlatform::NoClean,
+        prefix: config.status_prefix.clone(),
+        status_if_no_fronters: config.status_no_fronts.clone(),
+        truncate_names_to_length_if_status_too_long: config.status_truncate_names_to,
+        templates: config.status_templates_vrchat.clone(),
+    };
+
+    plurality::format_fronting_status(&fronting_format, fronts)
+}
+
+/// All OSC messages, which represent the current fronts.
+fn osc_messages(
+    config: &users::UserConfigForUpdater,
+    chatbox_text: &str,
+    fronts: &[plurality::Fronter],
+) -> Vec<Vec<u8>> {
+    let parameters = config.vrchat_osc_avatar_parameters.iter().map(|p| {
+        let fronting = fronts
+            .iter()
+            .any(|f| f.id == p.fronter || f.name == p.fronter);
+        avatar_parameter_message(&p.parameter, fronting)
+    });
+
+    std::iter::once(chatbox_message(chatbox_text))
+        .chain(parameters)
+        .collect()
+}
+
+/// Sends the fronting status to the chatbox and avatar parameters via OSC.
+/// This only works, when running on the same machine or network as the game client.
+pub struct VRChatOscUpdater {
+    pub last_operation_error: Option<updater::UpdaterError>,
+    pub last_pushed_status: Option<updater::PushedStatus>,
+    // the messages are resent periodically by a background task, until this is dropped.
+    messages: Option<watch::Sender<Vec<Vec<u8>>>>,
+}
+
+impl VRChatOscUpdater {
+    pub const fn new(_platform: updater::Platform) -> Self {
+        Self {
+            last_operation_error: None,
+            last_pushed_status: None,
+            messages: None,
+        }
+    }
+
+    pub async fn setup(&mut self, config: &users::UserConfigForUpdater) -> Result<()> {
+        let socket = record_if_error!(
+            self,
+            connect_osc_socket(&config.vrchat_osc_host, config.vrchat_osc_port).await
+        )?;
+
+        let (sender, receiver) = watch::channel(vec![]);
+        tokio::spawn(resend_osc_messages(Arc::new(socket), receiver));
+        self.messages = Some(sender);
+
+        Ok(())
+    }
+
+    #[allow(clippy::unused_async)]
+    pub async fn update_fronting_status(
+        &mut self,
+        config: &users::UserConfigForUpdater,
+        fronts: &[plurality::Fronter],
+    ) -> Result<()> {
+        let messages = record_if_error!(
+            self,
+            self.messages
+                .as_ref()
+                .ok_or_else(|| anyhow!("Updater not initalized!"))
+        )?;
+
+        let chatbox_text = format_vrchat_chatbox(config, fronts);
+
+        // the background task sends the new messages right away
+        messages.send_replace(osc_messages(config, &chatbox_text, fronts));
+
+        if self
+            .last_pushed_status
+            .as_ref()
+            .is_none_or(|s| s.value != chatbox_text)
+        {
+            eprintln!("VRChat chatbox updated to: '{chatbox_text}'");
+            self.last_pushed_status = Some(updater::PushedStatus::now(chatbox_text));
+        }
+
+        Ok(())
+    }
+}
+
+async fn connect_osc_socket(host: &str, port: u16) -> Result<UdpSocket> {
+    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+    socket.connect((host, port)).await?;
+    Ok(socket)
+}
+
+async fn resend_osc_messages(socket: Arc<UdpSocket>, mut messages: watch::Receiver<Vec<Vec<u8>>>) {
+    let mut resend = tokio::time::interval(CHATBOX_RESEND_INTERVAL);
+
+    loop {
+        tokio::select! {
+            changed = messages.changed() => {
+                // the updater was dropped, e.g. on restarts
+                if changed.is_err() {
+                    return;
+                }
+            }
+            _ = resend.tick() => {}
+        }
+
+        let current_messages = messages.borrow_and_update().clone();
+        for message in current_messages {
+            if let Err(e) = socket.send(&message).await {
+                eprintln!("Failed to send VRChat OSC message: {e}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::timeout;
+
+    const TEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[test]
+    fn test_osc_messages_are_padded_to_four_bytes() {
+        assert_eq!(
+            encode_osc_message("/a", &[OscArgument::Bool(true)]),
+            b"/a\0\0,T\0\0".to_vec()
+        );
+        assert_eq!(
+            encode_osc_message(
+                "/abc",
+                &[
+                    OscArgument::String("xyz".to_owned()),
+                    OscArgument::Bool(false)
+                ]
+            ),
+            b"/abc\0\0\0\0,sF\0xyz\0".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_avatar_parameters_must_be_valid_osc_addresses() {
+        let parameter = |name: &str| VRChatOscAvatarParameter {
+            fronter: "Alice".to_owned(),
+            parameter: name.to_owned(),
+        };
+
+        assert!(validate_vrchat_osc_avatar_parameters(vec![parameter("AliceFronting")]).is_ok());
+        assert!(validate_vrchat_osc_avatar_parameters(vec![parameter("")]).is_err());
+        assert!(validate_vrchat_osc_avatar_parameters(vec![parameter("Alice Fronting")]).is_err());
+        assert!(validate_vrchat_osc_avatar_parameters(vec![parameter("Alice*")]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_chatbox_and_avatar_parameters_are_received_via_udp() -> Result<()> {
+        let vrchat = UdpSocket::bind("127.0.0.1:0").await?;
+        let sender = connect_osc_socket("127.0.0.1", vrchat.local_addr()?.port()).await?;
+
+        sender.send(&chatbox_message("F: Alice")).await?;
+        sender
+            .send(&avatar_parameter_message("AliceFronting", true))
+            .await?;
+
+        let mut buffer = [0; 1024];
+
+        let length = timeout(TEST_TIMEOUT, vrchat.recv(&mut buffer)).await??;
+        assert_eq!(
+            &buffer[..length],
+            b"/chatbox/input\0\0,sTF\0\0\0\0F: Alice\0\0\0\0".as_slice()
+        );
+
+        let length = timeout(TEST_TIMEOUT, vrchat.recv(&mut buffer)).await??;
+        assert_eq!(
+            &buffer[..length],
+            b"/avatar/parameters/AliceFronting\0\0\0\0,T\0\0".as_slice()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_messages_are_sent_on_change() -> Result<()> {
+        let vrchat = UdpSocket::bind("127.0.0.1:0").await?;
+        let socket = connect_osc_socket("127.0.0.1", vrchat.local_addr()?.port()).await?;
+        let (sender, receiver) = watch::channel(vec![]);
+        let task = tokio::spawn(resend_osc_messages(Arc::new(socket), receiver));
+
+        sender.send_replace(vec![chatbox_message("F: Bob")]);
+
+        let mut buffer = [0; 1024];
+        let length = timeout(TEST_TIMEOUT, vrchat.recv(&mut buffer)).await??;
+        assert_eq!(&buffer[..length], chatbox_message("F: Bob").as_slice());
+
+        // the task ends with the updater
+        drop(sender);
+        timeout(TEST_TIMEOUT, task).await??;
+
+        Ok(())
+    }
+}
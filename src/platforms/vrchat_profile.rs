@@ -0,0 +1,201 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use vrchatapi::models as vrc;
+
+use crate::{plurality, users};
+
+pub const VRCHAT_BIO_MAX_LENGTH: usize = 512;
+
+// sp2any only changes the part of the bio between these markers
+pub const VRCHAT_BIO_SECTION_START: &str = "[fronting]";
+pub const VRCHAT_BIO_SECTION_END: &str = "[/fronting]";
+
+/// The status state, which is shown as the colored dot next to the name in game.
+/// `Offline` can't be set by users and is hence missing.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum VRChatStatusState {
+    Active,
+    JoinMe,
+    AskMe,
+    Busy,
+}
+
+/// The state, which is set while the fronter (member or custom front ID or name) is fronting.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct VRChatStatusStateMapping {
+    pub fronter: String,
+    pub state: VRChatStatusState,
+}
+
+impl From<VRChatStatusState> for vrc::UserStatus {
+    fn from(state: VRChatStatusState) -> Self {
+        match state {
+            VRChatStatusState::Active => Self::Active,
+            VRChatStatusState::JoinMe => Self::JoinMe,
+            VRChatStatusState::AskMe => Self::AskMe,
+            VRChatStatusState::Busy => Self::Busy,
+        }
+    }
+}
+
+/// Everything besides the status description, which the updater sets for the fronts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VRChatProfile {
+    // None: leave the state as it is
+    pub state: Option<VRChatStatusState>,
+    // None: leave the bio as it is
+    pub bio_section: Option<String>,
+}
+
+impl VRChatProfile {
+    pub fn for_fronts(config: &users::UserConfigForUpdater, fronts: &[plurality::Fronter]) -> Self {
+        Self {
+            state: vrchat_status_state(&config.vrchat_status_states, fronts),
+            bio_section: config
+                .vrchat_bio_template
+                .as_ref()
+                .map(|template| format_vrchat_bio_section(config, template, fronts)),
+        }
+    }
+}
+
+/// The state of the first fronter with a mapping. Once mappings are configured,
+/// the state returns to `Active`, when none of them is fronting anymore.
+pub fn vrchat_status_state(
+    mappings: &[VRChatStatusStateMapping],
+    fronts: &[plurality::Fronter],
+) -> Option<VRChatStatusState> {
+    if mappings.is_empty() {
+        return None;
+    }
+
+    let state = fronts
+        .iter()
+        .find_map(|f| {
+            mappings
+                .iter()
+                .find(|m| m.fronter == f.id || m.fronter == f.name)
+        })
+        .map_or(VRChatStatusState::Active, |m| m.state);
+
+    Some(state)
+}
+
+pub fn format_vrchat_bio_section(
+    config: &users::UserConfigForUpdater,
+    template: &plurality::StatusTemplate,
+    fronts: &[plurality::Fronter],
+) -> String {
+    // the bio shows all unicode characters and has its length checked when splicing
+    let fronting_format = plurality::FrontingFormat {
+        max_length: None,
+        length_measure: plurality::LengthMeasure::Chars,
+        cleaning: plurality::CleanForPlatform::NoClean,
+        prefix: config.status_prefix.clone(),
+        status_if_no_fronters: config.status_no_fronts.clone(),
+        truncate_names_to_length_if_status_too_long: config.status_truncate_names_to,
+        templates: vec![template.clone()],
+    };
+
+    plurality::format_fronting_status(&fronting_format, fronts)
+}
+
+/// Replaces the fronting section of the bio and leaves the rest untouched.
+/// The section is appended, if the bio doesn't have one yet.
+pub fn splice_vrchat_bio(bio: &str, section: &str) -> Result<String> {
+    let new_section = format!("{VRCHAT_BIO_SECTION_START}\n{section}\n{VRCHAT_BIO_SECTION_END}");
+
+    let existing_section = bio.find(VRCHAT_BIO_SECTION_START).and_then(|start| {
+        let end = bio[start..].find(VRCHAT_BIO_SECTION_END)?;
+        Some((start, start + end + VRCHAT_BIO_SECTION_END.len()))
+    });
+
+    let new_bio = match existing_section {
+        Some((start, end)) => format!("{}{new_section}{}", &bio[..start], &bio[end..]),
+        None if bio.trim().is_empty() => new_section,
+        None => format!("{}\n\n{new_section}", bio.trim_end()),
+    };
+
+    let length = new_bio.chars().count();
+    if length > VRCHAT_BIO_MAX_LENGTH {
+        return Err(anyhow!(
+            "The VRChat bio would be {length} characters long, but at most {VRCHAT_BIO_MAX_LENGTH} are allowed. Shorten the bio or the bio template."
+        ));
+    }
+
+    Ok(new_bio)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bio_section_is_replaced_and_rest_is_kept() -> Result<()> {
+        assert_eq!(
+            splice_vrchat_bio("Hi!\n[fronting]\nAlice\n[/fronting]\nBye!", "Bob")?,
+            "Hi!\n[fronting]\nBob\n[/fronting]\nBye!"
+        );
+        assert_eq!(
+            splice_vrchat_bio("Hi!\n", "Bob")?,
+            "Hi!\n\n[fronting]\nBob\n[/fronting]"
+        );
+        assert_eq!(
+            splice_vrchat_bio("", "Bob")?,
+            "[fronting]\nBob\n[/fronting]"
+        );
+
+        // an unclosed section is left alone
+        assert_eq!(
+            splice_vrchat_bio("Hi! [fronting]", "Bob")?,
+            "Hi! [fronting]\n\n[fronting]\nBob\n[/fronting]"
+        );
+
+        assert!(splice_vrchat_bio(&"a".repeat(VRCHAT_BIO_MAX_LENGTH), "Bob").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_status_state_follows_first_mapped_fronter() {
+        let mappings = vec![
+            VRChatStatusStateMapping {
+                fronter: "Sleeping".to_owned(),
+                state: VRChatStatusState::Busy,
+            },
+            VRChatStatusStateMapping {
+                fronter: "alice".to_owned(),
+                state: VRChatStatusState::JoinMe,
+            },
+        ];
+
+        assert_eq!(
+            vrchat_status_state(
+                &mappings,
+                &[
+                    plurality::Fronter::for_tests("Bob"),
+                    plurality::Fronter::for_tests("Sleeping")
+                ]
+            ),
+            Some(VRChatStatusState::Busy)
+        );
+        assert_eq!(
+            vrchat_status_state(
+                &mappings,
+                &[
+                    plurality::Fronter::for_tests("Alice"),
+                    plurality::Fronter::for_tests("Sleeping")
+                ]
+            ),
+            Some(VRChatStatusState::JoinMe)
+        );
+        assert_eq!(
+            vrchat_status_state(&mappings, &[plurality::Fronter::for_tests("Bob")]),
+            Some(VRChatStatusState::Active)
+        );
+        assert_eq!(
+            vrchat_status_state(&[], &[plurality::Fronter::for_tests("Sleeping")]),
+            None
+        );
+    }
+}
@@ -1,62 +1,436 @@
 use crate::database;
 use crate::http::HttpResult;
+use crate::platforms::webview_avatars;
+use crate::platforms::webview_formats::{self, WebsiteFormat};
 use crate::plurality;
+use crate::updater;
 use crate::users;
-use crate::users::UserId;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
 use rocket::{
-    response::{self, content::RawHtml},
-    State,
+    http::{Accept, ContentType, Header},
+    response::{
+        content::{RawHtml, RawText},
+        stream::{Event, EventStream},
+        Redirect,
+    },
+    serde::json::Json,
+    Shutdown, State,
 };
 use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::broadcast;
 
-#[get("/api/fronting/<user_id>")]
-pub async fn get_api_fronting_by_user_id(
-    user_id: &str, // todo. actually use system name here instead of user-id
+#[derive(Responder)]
+pub enum FrontingPage {
+    // the age header reports how stale the fronts are
+    Html(RawHtml<String>, Header<'static>),
+    Json(Json<webview_formats::FrontingJson>, Header<'static>),
+    Text(RawText<String>, Header<'static>),
+    Svg((ContentType, String), Header<'static>),
+    Redirect(Box<Redirect>),
+}
+
+#[allow(clippy::too_many_arguments)]
+#[get("/fronting/<slug>?<token>&<format>")]
+pub async fn get_fronting_by_slug(
+    slug: &str,
+    token: Option<&str>,
+    format: Option<&str>,
+    accept: Option<&Accept>,
     db_pool: &State<PgPool>,
     application_user_secrets: &State<database::ApplicationUserSecrets>,
     client: &State<reqwest::Client>,
-) -> HttpResult<RawHtml<String>> {
-    eprintln!("GET /fronting/{user_id}.");
-
-    let user_id: UserId = user_id.try_into()?;
+    shared_updaters: &State<updater::UpdaterManager>,
+) -> HttpResult<Option<FrontingPage>> {
+    eprintln!("GET /fronting/{slug}.");
 
-    eprintln!("GET /fronting/{user_id}. Getting user secrets");
+    // invalid slugs can't belong to anyone
+    let Ok(slug) = users::WebsiteSlug::try_from(slug.to_owned()) else {
+        return Ok(None);
+    };
 
-    let user_config =
-        database::get_user_secrets(db_pool, &user_id, application_user_secrets).await?;
+    let Some(user_id) = database::get_user_id_by_website_slug(db_pool, &slug).await? else {
+        let redirect =
+            database::get_website_slug_redirect(db_pool, &slug)
+                .await?
+                .map(|current_slug| {
+                    eprintln!("GET /fronting/{}. Redirecting to new slug.", slug.inner);
+                    FrontingPage::Redirect(Box::new(Redirect::temporary(uri!(
+                        get_fronting_by_slug(current_slug.inner, token, format)
+                    ))))
+                });
+        return Ok(redirect);
+    };
 
-    eprintln!("GET /fronting/{user_id}. Creating config");
+    let Some(website_format) = WebsiteFormat::negotiate(format, accept) else {
+        return Ok(None);
+    };
 
-    let (updater_config, _) =
-        users::create_config_with_strong_constraints(&user_id, client, &user_config)?;
+    let Some(updater_config) = website_config(
+        &user_id,
+        token,
+        db_pool,
+        application_user_secrets,
+        client,
+        shared_updaters,
+    )
+    .await?
+    else {
+        return Ok(None);
+    };
 
-    eprintln!("GET /fronting/{user_id}. Fetching fronts");
+    let cached_fronts = shared_updaters
+        .get_or_fetch_fronts(&user_id, &updater_config)
+        .await?;
 
-    let fronts = plurality::fetch_fronts(&updater_config)
-        .await
-        .map_err(response::Debug)?;
+    eprintln!("GET /fronting/{user_id}. Rendering {website_format:?}");
 
-    eprintln!("GET /fronting/{user_id}. Rendering HTML");
+    let age = Header::new("Age", cached_fronts.age().as_secs().to_string());
+    let visible_fronts = visible_fronts(
+        &cached_fronts.fronts,
+        &updater_config.website_hidden_fronters,
+    );
 
-    let html = generate_html(&updater_config.system_name, fronts);
+    let page = match website_format {
+        WebsiteFormat::Html => FrontingPage::Html(
+            RawHtml(generate_html(
+                &updater_config,
+                &slug,
+                token,
+                &visible_fronts,
+                cached_fronts.fetched_at,
+            )),
+            age,
+        ),
+        WebsiteFormat::Json => FrontingPage::Json(
+            Json(webview_formats::FrontingJson {
+                version: webview_formats::FRONTING_JSON_VERSION,
+                system_name: updater_config.system_name.clone(),
+                fetched_at: cached_fronts.fetched_at,
+                fronters: visible_fronts
+                    .iter()
+                    .map(|f| {
+                        let avatar_url = avatar_proxy_url(&updater_config, &slug, token, f);
+                        webview_formats::FronterJson::new(f, avatar_url)
+                    })
+                    .collect(),
+                statuses: platform_statuses(
+                    &updater::available_updaters(shared_updaters),
+                    &updater_config,
+                    &visible_fronts,
+                ),
+            }),
+            age,
+        ),
+        WebsiteFormat::Text => FrontingPage::Text(
+            RawText(webview_formats::generate_text(
+                &visible_fronts,
+                &updater_config.status_no_fronts,
+            )),
+            age,
+        ),
+        WebsiteFormat::Svg => FrontingPage::Svg(
+            (
+                ContentType::SVG,
+                webview_formats::generate_svg(&visible_fronts, &updater_config.status_no_fronts),
+            ),
+            age,
+        ),
+    };
 
     eprintln!("GET /fronting/{user_id}. OK");
-    Ok(RawHtml(html))
+    Ok(Some(page))
+}
+
+/// Pushes the re-rendered fronts to the page, whenever the updater of the user observes a change.
+#[get("/fronting/<slug>/events?<token>")]
+pub async fn get_fronting_events_by_slug(
+    slug: &str,
+    token: Option<&str>,
+    db_pool: &State<PgPool>,
+    application_user_secrets: &State<database::ApplicationUserSecrets>,
+    client: &State<reqwest::Client>,
+    shared_updaters: &State<updater::UpdaterManager>,
+    mut shutdown: Shutdown,
+) -> HttpResult<Option<EventStream![]>> {
+    eprintln!("GET /fronting/{slug}/events.");
+
+    let Ok(slug) = users::WebsiteSlug::try_from(slug.to_owned()) else {
+        return Ok(None);
+    };
+
+    let Some(user_id) = database::get_user_id_by_website_slug(db_pool, &slug).await? else {
+        return Ok(None);
+    };
+
+    if website_config(
+        &user_id,
+        token,
+        db_pool,
+        application_user_secrets,
+        client,
+        shared_updaters,
+    )
+    .await?
+    .is_none()
+    {
+        return Ok(None);
+    }
+
+    let mut front_changes = shared_updaters.subscribe_front_changes(&user_id)?;
+    let token = token.map(ToOwned::to_owned);
+    let db_pool = db_pool.inner().clone();
+    let application_user_secrets = application_user_secrets.inner().clone();
+    let client = client.inner().clone();
+    let shared_updaters = shared_updaters.inner().clone();
+
+    Ok(Some(EventStream! {
+        loop {
+            let cached_fronts = tokio::select! {
+                received = front_changes.recv() => match received {
+                    Ok(cached_fronts) => cached_fronts,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                () = &mut shutdown => break,
+            };
+
+            // the user may have hidden fronters, disabled the website or changed the token meanwhile
+            let updater_config = match website_config(
+                &user_id,
+                token.as_deref(),
+                &db_pool,
+                &application_user_secrets,
+                &client,
+                &shared_updaters,
+            )
+            .await
+            {
+                Ok(Some(updater_config)) => updater_config,
+                Ok(None) => break,
+                Err(e) => {
+                    eprintln!("GET /fronting/{user_id}/events. Error: {e}");
+                    break;
+                }
+            };
+
+            let visible_fronts =
+                visible_fronts(&cached_fronts.fronts, &updater_config.website_hidden_fronters);
+            yield Event::data(generate_fronts_html(
+                &updater_config,
+                &slug,
+                token.as_deref(),
+                &visible_fronts,
+                cached_fronts.fetched_at,
+            ))
+            .event("fronts");
+        }
+    }))
 }
 
-fn generate_html(system_name: &str, fronts: Vec<plurality::Fronter>) -> String {
+/// Serves the avatar of a currently shown fronter, so that viewers don't fetch it from third parties.
+#[allow(clippy::too_many_arguments)]
+#[get("/fronting/<slug>/avatars/<fronter_id>?<token>")]
+pub async fn get_fronting_avatar_by_slug(
+    slug: &str,
+    fronter_id: &str,
+    token: Option<&str>,
+    db_pool: &State<PgPool>,
+    application_user_secrets: &State<database::ApplicationUserSecrets>,
+    client: &State<reqwest::Client>,
+    avatar_client: &State<webview_avatars::AvatarHttpClient>,
+    shared_updaters: &State<updater::UpdaterManager>,
+) -> HttpResult<Option<(ContentType, Vec<u8>)>> {
+    eprintln!("GET /fronting/{slug}/avatars/{fronter_id}.");
+
+    let Ok(slug) = users::WebsiteSlug::try_from(slug.to_owned()) else {
+        return Ok(None);
+    };
+
+    let Some(user_id) = database::get_user_id_by_website_slug(db_pool, &slug).await? else {
+        return Ok(None);
+    };
+
+    let Some(updater_config) = website_config(
+        &user_id,
+        token,
+        db_pool,
+        application_user_secrets,
+        client,
+        shared_updaters,
+    )
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let cached_fronts = shared_updaters
+        .get_or_fetch_fronts(&user_id, &updater_config)
+        .await?;
+
+    // only avatars shown on the page are served. otherwise, this would be an open proxy.
+    let Some(source_url) = visible_fronts(
+        &cached_fronts.fronts,
+        &updater_config.website_hidden_fronters,
+    )
+    .into_iter()
+    .find(|f| f.id == fronter_id && !f.avatar_url.is_empty())
+    .map(|f| f.avatar_url)
+    .filter(|_| updater_config.website_show_avatars) else {
+        return Ok(None);
+    };
+
+    match webview_avatars::get_avatar(db_pool, avatar_client, &source_url).await {
+        Ok(avatar) => {
+            eprintln!("GET /fronting/{user_id}/avatars/{fronter_id}. OK");
+            Ok(Some((ContentType::PNG, avatar)))
+        }
+        Err(e) => {
+            // a broken avatar is shown as a missing one
+            eprintln!("GET /fronting/{user_id}/avatars/{fronter_id}. Error: {e}");
+            Ok(None)
+        }
+    }
+}
+
+/// The config of the user, if the website is enabled and may be accessed with the token.
+async fn website_config(
+    user_id: &users::UserId,
+    token: Option<&str>,
+    db_pool: &PgPool,
+    application_user_secrets: &database::ApplicationUserSecrets,
+    client: &reqwest::Client,
+    shared_updaters: &updater::UpdaterManager,
+) -> Result<Option<Arc<users::UserConfigForUpdater>>> {
+    let updater_config = if let Some(cached) = shared_updaters.get_cached_website_config(user_id)? {
+        cached
+    } else {
+        eprintln!("GET /fronting/{user_id}. Getting user secrets");
+
+        let user_config =
+            database::get_user_secrets(db_pool, user_id, application_user_secrets).await?;
+
+        eprintln!("GET /fronting/{user_id}. Creating config");
+
+        let (updater_config, _) =
+            users::create_config_with_strong_constraints(user_id, client, &user_config)?;
+        let updater_config = Arc::new(updater_config);
+        shared_updaters.set_cached_website_config(user_id, updater_config.clone())?;
+        updater_config
+    };
+
+    // disabled and protected pages are indistinguishable from non-existing ones
+    if !updater_config.enable_website
+        || !is_access_allowed(&updater_config.website_access_token.secret, token)
+    {
+        eprintln!("GET /fronting/{user_id}. Website disabled or access denied.");
+        return Ok(None);
+    }
+
+    Ok(Some(updater_config))
+}
+
+pub fn visible_fronts(
+    fronts: &[plurality::Fronter],
+    hidden_fronters: &[String],
+) -> Vec<plurality::Fronter> {
+    fronts
+        .iter()
+        .filter(|f| is_visible_on_website(f, hidden_fronters))
+        .cloned()
+        .collect()
+}
+
+// only the platforms, which the user enabled
+fn platform_statuses(
+    platforms: &[updater::Platform],
+    config: &users::UserConfigForUpdater,
+    fronts: &[plurality::Fronter],
+) -> HashMap<updater::Platform, String> {
+    platforms
+        .iter()
+        .filter(|p| p.enabled(config))
+        .map(|p| (p.to_owned(), p.format_status(config, fronts)))
+        .collect()
+}
+
+pub fn is_access_allowed(website_access_token: &str, token: Option<&str>) -> bool {
+    website_access_token.is_empty() || token == Some(website_access_token)
+}
+
+fn is_visible_on_website(fronter: &plurality::Fronter, hidden_fronters: &[String]) -> bool {
+    !fronter.private
+        && !hidden_fronters
+            .iter()
+            .any(|hidden| hidden == &fronter.id || hidden == &fronter.name)
+}
+
+// avatars are served by the website, such that viewers don't fetch them from third parties
+fn avatar_proxy_url(
+    config: &users::UserConfigForUpdater,
+    slug: &users::WebsiteSlug,
+    token: Option<&str>,
+    fronter: &plurality::Fronter,
+) -> Option<String> {
+    if !config.website_show_avatars || fronter.avatar_url.is_empty() {
+        return None;
+    }
+
+    Some(uri!(get_fronting_avatar_by_slug(&slug.inner, &fronter.id, token)).to_string())
+}
+
+// the part of the page, which is re-rendered on front changes
+fn generate_fronts_html(
+    config: &users::UserConfigForUpdater,
+    slug: &users::WebsiteSlug,
+    token: Option<&str>,
+    fronts: &[plurality::Fronter],
+    fetched_at: DateTime<Utc>,
+) -> String {
     let fronts_formatted = fronts
-        .into_iter()
+        .iter()
         .map(|m| -> String {
+            // colors are normalized hex colors and hence safe to embed
+            let style = m
+                .color
+                .as_ref()
+                .filter(|_| config.website_show_member_colors)
+                .map(|color| format!(" style=\"border-left:.5rem solid {color}\""))
+                .unwrap_or_default();
+            let avatar = if config.website_show_avatars {
+                // if URL is empty, then simply no image is rendered.
+                let avatar_url = avatar_proxy_url(config, slug, token, m).unwrap_or_default();
+                format!(
+                    "<img src=\"{}\" />",
+                    html_escape::encode_double_quoted_attribute(&avatar_url)
+                )
+            } else {
+                String::new()
+            };
             format!(
-                "<div><img src=\"{}\" /><p>{}</p></div>",
-                m.avatar_url, // if URL is empty, then simply no image is rendered.
+                "<div{style}>{avatar}<p>{}</p></div>",
                 html_escape::encode_text(&m.name)
             )
         })
         .collect::<Vec<String>>()
         .join("\n");
 
+    format!(
+        "{}\n<footer>Last updated {} UTC</footer>",
+        fronts_formatted,
+        fetched_at.format("%Y-%m-%d %H:%M:%S")
+    )
+}
+
+fn generate_html(
+    config: &users::UserConfigForUpdater,
+    slug: &users::WebsiteSlug,
+    token: Option<&str>,
+    fronts: &[plurality::Fronter],
+    fetched_at: DateTime<Utc>,
+) -> String {
     format!(
         r"<html>
     <head>
@@ -68,13 +442,15 @@ fn generate_html(system_name: &str, fronts: Vec<plurality::Fronter>) -> String {
                 margin:0;
                 padding:1rem;
                 font-family:sans-serif;
+            }}
+            main{{
                 display:flex;
                 flex-direction: column;
                 gap:1rem;
             }}
 
             /* --- one card -------------------------------------------- */
-            body>div {{
+            main>div {{
                 flex:1 1 calc(25% - 1rem);   /* ≤4 cards per row */
                 display:flex;
                 align-items:center;
@@ -86,7 +462,7 @@ fn generate_html(system_name: &str, fronts: Vec<plurality::Fronter>) -> String {
             }}
 
             /* --- avatar image ---------------------------------------- */
-            body>div img {{
+            main>div img {{
                 width:10rem;
                 height:10rem;           /* fixed square keeps things tidy */
                 object-fit:cover;
@@ -94,7 +470,7 @@ fn generate_html(system_name: &str, fronts: Vec<plurality::Fronter>) -> String {
             }}
 
             /* --- name ------------------------------------------------- */
-            body>div p {{
+            main>div p {{
                 margin:0;
                 font-size: 3rem;
                 font-weight:600;
@@ -102,18 +478,197 @@ fn generate_html(system_name: &str, fronts: Vec<plurality::Fronter>) -> String {
 
             /* --- phones & tablets ------------------------------------ */
             @media (max-width:800px) {{
-                body>div {{flex:1 1 calc(50% - 1rem);}}   /* 2-across */
+                main>div {{flex:1 1 calc(50% - 1rem);}}   /* 2-across */
             }}
             @media (max-width:420px) {{
-                body>div {{flex:1 1 100%;}}               /* stack */
+                main>div {{flex:1 1 100%;}}               /* stack */
             }}
         </style>
+        <style>
+            {}
+            {}
+        </style>
+        <style>{}</style>
     </head>
     <body>
+        <main id='fronts'>
         {}
+        </main>
+        <script>
+            // re-render on front changes. the browser reconnects by itself on connection losses.
+            const events = new EventSource(
+                window.location.pathname + '/events' + window.location.search
+            );
+            events.addEventListener('fronts', (event) => {{
+                document.getElementById('fronts').innerHTML = event.data;
+            }});
+        </script>
     </body>
 </html>",
-        html_escape::encode_text(system_name),
-        fronts_formatted
+        html_escape::encode_text(&config.system_name),
+        config.website_theme.css(),
+        config.website_layout.css(),
+        config.website_custom_css,
+        generate_fronts_html(config, slug, token, fronts, fetched_at)
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platforms;
+
+    #[test]
+    fn test_hidden_and_private_fronters_are_not_visible() {
+        let hidden = vec![String::from("id-b"), String::from("Carol")];
+
+        assert!(is_visible_on_website(
+            &plurality::Fronter::for_tests("Alice").with_id("id-a"),
+            &hidden
+        ));
+        assert!(!is_visible_on_website(
+            &plurality::Fronter::for_tests("Bob").with_id("id-b"),
+            &hidden
+        ));
+        assert!(!is_visible_on_website(
+            &plurality::Fronter::for_tests("Carol").with_id("id-c"),
+            &hidden
+        ));
+        assert!(!is_visible_on_website(
+            &plurality::Fronter {
+                private: true,
+                ..plurality::Fronter::for_tests("Dave").with_id("id-d")
+            },
+            &hidden
+        ));
+    }
+
+    #[test]
+    fn test_access_token_is_only_required_when_configured() {
+        assert!(is_access_allowed("", None));
+        assert!(is_access_allowed("", Some("anything")));
+        assert!(is_access_allowed("secret", Some("secret")));
+        assert!(!is_access_allowed("secret", Some("wrong")));
+        assert!(!is_access_allowed("secret", None));
+    }
+
+    #[test]
+    fn test_fronts_are_rendered_with_appearance_settings() -> Result<()> {
+        let alice = plurality::Fronter {
+            avatar_url: String::from("https://example.com/a"),
+            color: Some(String::from("#ff0000")),
+            ..plurality::Fronter::for_tests("Alice").with_id("id-a")
+        };
+        let fetched_at = DateTime::<Utc>::default();
+        let slug = users::WebsiteSlug::try_from(String::from("my-system"))?;
+
+        let default_config = users::config_for_tests(&users::UserConfigDbEntries::default())?;
+        let html = generate_fronts_html(
+            &default_config,
+            &slug,
+            None,
+            std::slice::from_ref(&alice),
+            fetched_at,
+        );
+        assert!(html
+            .contains("<div><img src=\"/fronting/my-system/avatars/id-a\" /><p>Alice</p></div>"));
+
+        let customized_config = users::config_for_tests(&users::UserConfigDbEntries {
+            website_show_avatars: Some(false),
+            website_show_member_colors: Some(true),
+            website_theme: Some(platforms::WebsiteTheme::Transparent),
+            website_custom_css: Some(String::from("p { color: red; }")),
+            ..Default::default()
+        })?;
+        let html = generate_html(&customized_config, &slug, None, &[alice], fetched_at);
+        assert!(html.contains("<div style=\"border-left:.5rem solid #ff0000\"><p>Alice</p></div>"));
+        assert!(html.contains("background:transparent"));
+        assert!(html.contains("<style>p { color: red; }</style>"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_avatars_are_served_via_the_proxy_with_escaped_urls() -> Result<()> {
+        let fronter = plurality::Fronter {
+            avatar_url: String::from("https://example.com/\"onerror=\"alert(1)"),
+            ..plurality::Fronter::for_tests("Alice").with_id("id\" onerror=\"alert(1)")
+        };
+        let config = users::config_for_tests(&users::UserConfigDbEntries::default())?;
+        let slug = users::WebsiteSlug::try_from(String::from("my-system"))?;
+
+        let html = generate_fronts_html(
+            &config,
+            &slug,
+            Some("secret&token"),
+            &[fronter],
+            DateTime::<Utc>::default(),
+        );
+
+        assert!(!html.contains("example.com"));
+        assert!(!html.contains("\" onerror"));
+        assert!(html.contains(
+            "<img src=\"/fronting/my-system/avatars/id%22%20onerror%3D%22alert(1)?token=secret%26token\" />"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_avatar_urls_point_to_the_proxy() -> Result<()> {
+        let alice = plurality::Fronter {
+            avatar_url: String::from("https://example.com/a"),
+            ..plurality::Fronter::for_tests("Alice").with_id("id-a")
+        };
+        let bob = plurality::Fronter::for_tests("Bob").with_id("id-b");
+        let config = users::config_for_tests(&users::UserConfigDbEntries::default())?;
+        let slug = users::WebsiteSlug::try_from(String::from("my-system"))?;
+
+        assert_eq!(
+            avatar_proxy_url(&config, &slug, Some("secret"), &alice),
+            Some(String::from(
+                "/fronting/my-system/avatars/id-a?token=secret"
+            ))
+        );
+        assert_eq!(
+            avatar_proxy_url(&config, &slug, None, &alice),
+            Some(String::from("/fronting/my-system/avatars/id-a"))
+        );
+        assert_eq!(avatar_proxy_url(&config, &slug, None, &bob), None);
+
+        let hidden_avatars = users::config_for_tests(&users::UserConfigDbEntries {
+            website_show_avatars: Some(false),
+            ..Default::default()
+        })?;
+        assert_eq!(avatar_proxy_url(&hidden_avatars, &slug, None, &alice), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_statuses_only_include_enabled_platforms() -> Result<()> {
+        let config = users::config_for_tests(&users::UserConfigDbEntries {
+            enable_vrchat: Some(true),
+            vrchat_username: Some("user".into()),
+            vrchat_password: Some("password".into()),
+            enable_discord_status_message: Some(false),
+            ..Default::default()
+        })?;
+
+        let statuses = platform_statuses(
+            &[
+                updater::Platform::VRChat,
+                updater::Platform::DiscordStatusMessage,
+            ],
+            &config,
+            &[plurality::Fronter::for_tests("Alice")],
+        );
+
+        assert_eq!(
+            statuses.keys().collect::<Vec<_>>(),
+            vec![&updater::Platform::VRChat]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,294 @@
+use anyhow::{anyhow, Result};
+use image::{ImageFormat, ImageReader, Limits};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use sqlx::PgPool;
+use std::io::Cursor;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::database;
+
+/// Avatars are shown at most at 10rem. 256px keeps them sharp on high density screens.
+pub const AVATAR_MAX_DIMENSION: u32 = 256;
+
+// refetch avatars once a day, so that changed images at the same URL show up eventually
+const AVATAR_CACHE_MAX_AGE_SECONDS: i64 = 24 * 60 * 60;
+
+const AVATAR_MAX_DOWNLOAD_BYTES: usize = 5 * 1024 * 1024;
+
+// guards against decompression bombs, which are small downloads but huge images
+const AVATAR_MAX_DECODED_BYTES: u64 = 64 * 1024 * 1024;
+const AVATAR_MAX_SOURCE_DIMENSION: u32 = 8192;
+
+const ALLOWED_AVATAR_FORMATS: [(&str, ImageFormat); 5] = [
+    ("image/png", ImageFormat::Png),
+    ("image/jpeg", ImageFormat::Jpeg),
+    ("image/jpg", ImageFormat::Jpeg),
+    ("image/webp", ImageFormat::WebP),
+    ("image/gif", ImageFormat::Gif),
+];
+
+/// Fetches the avatars, whose URLs are chosen by the users. Hence, it only connects to public addresses
+/// via https, doesn't follow redirects and sends no cookies.
+#[derive(Clone)]
+pub struct AvatarHttpClient {
+    pub inner: reqwest::Client,
+}
+
+impl AvatarHttpClient {
+    pub fn new(timeout: Duration) -> Result<Self> {
+        let inner = reqwest::Client::builder()
+            .https_only(true)
+            .redirect(reqwest::redirect::Policy::none())
+            // a proxy would resolve the host itself
+            .no_proxy()
+            .dns_resolver(Arc::new(PublicAddressResolver))
+            .timeout(timeout)
+            .build()?;
+
+        Ok(Self { inner })
+    }
+}
+
+// the connection is made to the checked addresses, such that a second lookup can't return another one
+struct PublicAddressResolver;
+
+impl Resolve for PublicAddressResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| is_public_address(addr.ip()))
+                .collect();
+
+            if addrs.is_empty() {
+                return Err(
+                    anyhow!("Avatar host '{}' has no public address.", name.as_str()).into(),
+                );
+            }
+
+            let addrs: Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            // 100.64.0.0/10 is shared by carrier-grade NATs
+            let is_shared = ip.octets()[0] == 100 && ip.octets()[1] & 0b1100_0000 == 64;
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || is_shared)
+        }
+        IpAddr::V6(ip) => ip.to_ipv4_mapped().map_or_else(
+            || {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local())
+            },
+            |ip| is_public_address(IpAddr::V4(ip)),
+        ),
+    }
+}
+
+/// The avatar behind `source_url` as a PNG resized to `AVATAR_MAX_DIMENSION`.
+/// Fetches it only, if it isn't cached yet.
+pub async fn get_avatar(
+    db_pool: &PgPool,
+    client: &AvatarHttpClient,
+    source_url: &str,
+) -> Result<Vec<u8>> {
+    if let Some(avatar) =
+        database::get_cached_avatar(db_pool, source_url, AVATAR_CACHE_MAX_AGE_SECONDS).await?
+    {
+        return Ok(avatar);
+    }
+
+    eprintln!("Fetching avatar {source_url} ...");
+    let (content_type, bytes) = fetch_avatar(client, source_url).await?;
+    // decoding large images takes a while and would block the other requests
+    let avatar =
+        tokio::task::spawn_blocking(move || resize_avatar(&content_type, &bytes)).await??;
+
+    database::set_cached_avatar(db_pool, source_url, &avatar, AVATAR_CACHE_MAX_AGE_SECONDS).await?;
+
+    Ok(avatar)
+}
+
+async fn fetch_avatar(client: &AvatarHttpClient, source_url: &str) -> Result<(String, Vec<u8>)> {
+    let url = reqwest::Url::parse(source_url)?;
+    if url.scheme() != "https" {
+        return Err(anyhow!("Avatar URL '{source_url}' doesn't use https."));
+    }
+
+    // addresses in the URL aren't resolved and hence not checked by the resolver
+    let literal_ip = url
+        .host_str()
+        .map(|host| host.trim_start_matches('[').trim_end_matches(']'))
+        .and_then(|host| host.parse::<IpAddr>().ok());
+    if literal_ip.is_some_and(|ip| !is_public_address(ip)) {
+        return Err(anyhow!("Avatar URL '{source_url}' has no public address."));
+    }
+
+    let mut response = client.inner.get(url).send().await?.error_for_status()?;
+
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_owned();
+
+    // fail early instead of downloading anything which isn't an image
+    allowed_avatar_format(&content_type)?;
+
+    let mut bytes = vec![];
+    while let Some(chunk) = response.chunk().await? {
+        if bytes.len() + chunk.len() > AVATAR_MAX_DOWNLOAD_BYTES {
+            return Err(anyhow!(
+                "Avatar is larger than {AVATAR_MAX_DOWNLOAD_BYTES} bytes."
+            ));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+
+    Ok((content_type, bytes))
+}
+
+fn allowed_avatar_format(content_type: &str) -> Result<ImageFormat> {
+    // e.g. "image/png; charset=binary"
+    let media_type = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+
+    ALLOWED_AVATAR_FORMATS
+        .iter()
+        .find(|(allowed, _)| *allowed == media_type)
+        .map(|(_, format)| *format)
+        .ok_or_else(|| anyhow!("Avatar has unsupported content type '{content_type}'."))
+}
+
+/// Decodes the image and re-encodes it as a PNG no larger than `AVATAR_MAX_DIMENSION`.
+/// The content type must match the actual image format.
+pub fn resize_avatar(content_type: &str, bytes: &[u8]) -> Result<Vec<u8>> {
+    let expected_format = allowed_avatar_format(content_type)?;
+
+    let mut reader = ImageReader::new(Cursor::new(bytes)).with_guessed_format()?;
+    if reader.format() != Some(expected_format) {
+        return Err(anyhow!(
+            "Avatar content does not match its content type '{content_type}'."
+        ));
+    }
+
+    let mut limits = Limits::default();
+    limits.max_alloc = Some(AVATAR_MAX_DECODED_BYTES);
+    limits.max_image_width = Some(AVATAR_MAX_SOURCE_DIMENSION);
+    limits.max_image_height = Some(AVATAR_MAX_SOURCE_DIMENSION);
+    reader.limits(limits);
+
+    let mut image = reader.decode()?;
+    if image.width() > AVATAR_MAX_DIMENSION || image.height() > AVATAR_MAX_DIMENSION {
+        image = image.thumbnail(AVATAR_MAX_DIMENSION, AVATAR_MAX_DIMENSION);
+    }
+
+    let mut png = vec![];
+    image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, GenericImageView};
+
+    fn encoded_image(width: u32, height: u32, format: ImageFormat) -> Result<Vec<u8>> {
+        let mut bytes = vec![];
+        DynamicImage::new_rgb8(width, height).write_to(&mut Cursor::new(&mut bytes), format)?;
+        Ok(bytes)
+    }
+
+    #[test]
+    fn test_large_avatars_are_resized_to_png() -> Result<()> {
+        let jpeg = encoded_image(1024, 512, ImageFormat::Jpeg)?;
+
+        let avatar = resize_avatar("image/jpeg", &jpeg)?;
+
+        let resized = image::load_from_memory_with_format(&avatar, ImageFormat::Png)?;
+        assert_eq!(
+            resized.dimensions(),
+            (AVATAR_MAX_DIMENSION, AVATAR_MAX_DIMENSION / 2)
+        );
+
+        let small = resize_avatar("image/png", &encoded_image(32, 32, ImageFormat::Png)?)?;
+        assert_eq!(image::load_from_memory(&small)?.dimensions(), (32, 32));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_only_public_addresses_are_allowed() -> Result<()> {
+        for public in ["93.184.215.14", "2606:2800:21f:cb07:6820:80da:af6b:8b2c"] {
+            assert!(is_public_address(public.parse()?), "{public}");
+        }
+
+        for internal in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public_address(internal.parse()?), "{internal}");
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_internal_and_non_https_avatar_urls_are_not_fetched() -> Result<()> {
+        let client = AvatarHttpClient::new(Duration::from_secs(5))?;
+
+        for url in [
+            "http://example.com/a.png",
+            "https://127.0.0.1/a.png",
+            "https://[::1]/a.png",
+            "https://169.254.169.254/latest/meta-data",
+            "https://localhost/a.png",
+        ] {
+            assert!(fetch_avatar(&client, url).await.is_err(), "{url}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_non_images_and_mismatching_content_types_are_rejected() -> Result<()> {
+        let png = encoded_image(32, 32, ImageFormat::Png)?;
+
+        assert!(resize_avatar("image/png; charset=binary", &png).is_ok());
+        assert!(resize_avatar("text/html", &png).is_err());
+        assert!(resize_avatar("image/svg+xml", &png).is_err());
+        assert!(resize_avatar("image/jpeg", &png).is_err());
+        assert!(resize_avatar("image/png", b"<script>alert(1)</script>").is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+use chrono::{DateTime, Utc};
+use rocket::http::{Accept, MediaType};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::plurality;
+use crate::updater;
+
+/// Version of the JSON shape. Increase on breaking changes of `FrontingJson`.
+pub const FRONTING_JSON_VERSION: u32 = 1;
+
+const SVG_BADGE_LABEL: &str = "fronting";
+// rough average width of a character in the badge font
+const SVG_BADGE_CHAR_WIDTH: usize = 7;
+const SVG_BADGE_PADDING: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebsiteFormat {
+    Html,
+    Json,
+    Text,
+    Svg,
+}
+
+impl WebsiteFormat {
+    /// The format from the `?format=` query parameter. Falls back to the `Accept` header.
+    pub fn negotiate(format: Option<&str>, accept: Option<&Accept>) -> Option<Self> {
+        match format {
+            Some("html") => Some(Self::Html),
+            Some("json") => Some(Self::Json),
+            Some("text" | "txt") => Some(Self::Text),
+            Some("svg") => Some(Self::Svg),
+            Some(_) => None,
+            None => Some(Self::from_accept(accept)),
+        }
+    }
+
+    fn from_accept(accept: Option<&Accept>) -> Self {
+        let Some(media_type) = accept.map(|a| a.preferred().media_type()) else {
+            return Self::Html;
+        };
+
+        if media_type.is_json() {
+            Self::Json
+        } else if media_type.is_plain() {
+            Self::Text
+        } else if media_type == &MediaType::SVG {
+            Self::Svg
+        } else {
+            Self::Html
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct FrontingJson {
+    pub version: u32,
+    pub system_name: String,
+    pub fetched_at: DateTime<Utc>,
+    pub fronters: Vec<FronterJson>,
+    // the status strings as the updaters would push them for the shown fronters
+    pub statuses: HashMap<updater::Platform, String>,
+}
+
+#[derive(Serialize)]
+pub struct FronterJson {
+    pub id: String,
+    pub name: String,
+    pub avatar_url: Option<String>,
+    pub start_time: Option<DateTime<Utc>>,
+}
+
+impl FronterJson {
+    /// The avatar URL points to the avatar proxy of the website, not to the original source.
+    pub fn new(f: &plurality::Fronter, avatar_url: Option<String>) -> Self {
+        Self {
+            id: f.id.clone(),
+            name: f.name.clone(),
+            avatar_url,
+            start_time: f.start_time,
+        }
+    }
+}
+
+/// The fronter names in a single line. Shows `status_no_fronts`, if no one is fronting.
+pub fn generate_text(fronts: &[plurality::Fronter], status_no_fronts: &str) -> String {
+    if fronts.is_empty() {
+        return status_no_fronts.to_owned();
+    }
+
+    fronts
+        .iter()
+        .map(|f| f.name.as_str())
+        .collect::<Vec<&str>>()
+        .join(", ")
+}
+
+/// A badge in the style of the common README badges with the fronter names.
+pub fn generate_svg(fronts: &[plurality::Fronter], status_no_fronts: &str) -> String {
+    let value = generate_text(fronts, status_no_fronts);
+
+    let label_width = badge_text_width(SVG_BADGE_LABEL);
+    let value_width = badge_text_width(&value);
+    let width = label_width + value_width;
+
+    let label = html_escape::encode_safe(SVG_BADGE_LABEL);
+    let value = html_escape::encode_safe(&value);
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {value}">
+    <title>{label}: {value}</title>
+    <rect width="{label_width}" height="20" fill="#555"/>
+    <rect x="{label_width}" width="{value_width}" height="20" fill="#7e57c2"/>
+    <g fill="#fff" text-anchor="middle" font-family="Verdana,DejaVu Sans,sans-serif" font-size="11">
+        <text x="{label_center}" y="14">{label}</text>
+        <text x="{value_center}" y="14">{value}</text>
+    </g>
+</svg>"##,
+        label_center = label_width / 2,
+        value_center = label_width + value_width / 2,
+    )
+}
+
+fn badge_text_width(text: &str) -> usize {
+    text.chars().count() * SVG_BADGE_CHAR_WIDTH + 2 * SVG_BADGE_PADDING
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_query_parameter_wins_over_accept_header() {
+        let accept_json = Accept::from(MediaType::JSON);
+
+        assert_eq!(
+            WebsiteFormat::negotiate(Some("svg"), Some(&accept_json)),
+            Some(WebsiteFormat::Svg)
+        );
+        assert_eq!(
+            WebsiteFormat::negotiate(None, Some(&accept_json)),
+            Some(WebsiteFormat::Json)
+        );
+        assert_eq!(
+            WebsiteFormat::negotiate(None, None),
+            Some(WebsiteFormat::Html)
+        );
+        assert_eq!(WebsiteFormat::negotiate(Some("pdf"), None), None);
+    }
+
+    #[test]
+    fn test_text_and_svg_show_names_escaped() {
+        let fronts = vec![
+            plurality::Fronter::for_tests("Alice"),
+            plurality::Fronter::for_tests("<Bob & Co>"),
+        ];
+
+        assert_eq!(generate_text(&fronts, "none?"), "Alice, <Bob & Co>");
+        assert_eq!(generate_text(&[], "none?"), "none?");
+
+        let svg = generate_svg(&fronts, "none?");
+        assert!(svg.contains(">Alice, &lt;Bob &amp; Co&gt;</text>"));
+        assert!(!svg.contains("<Bob"));
+    }
+}
@@ -0,0 +1,150 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::sqlx_text_enum;
+
+const CUSTOM_CSS_MAX_LENGTH: usize = 10_000;
+
+// constructs which can load external resources or execute code. all at-rules ('@') are forbidden as well,
+// e.g. @import and @font-face, and escapes ('\') too, as they could hide any of these, e.g. `u\72l(`.
+const FORBIDDEN_CSS_CONSTRUCTS: [&str; 9] = [
+    "<",
+    "\\",
+    "@",
+    "url(",
+    "image-set(",
+    "src(",
+    "expression(",
+    "javascript:",
+    "behavior:",
+];
+
+/// Background and colors of the fronting page.
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    strum_macros::Display,
+    strum_macros::EnumString,
+    PartialEq,
+    Eq,
+)]
+pub enum WebsiteTheme {
+    #[default]
+    Light,
+    Dark,
+    // for stream overlays, e.g. as an OBS browser source
+    Transparent,
+}
+
+/// Arrangement of the fronters on the fronting page.
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    strum_macros::Display,
+    strum_macros::EnumString,
+    PartialEq,
+    Eq,
+)]
+pub enum WebsiteLayout {
+    #[default]
+    List,
+    Grid,
+    Compact,
+}
+
+impl WebsiteTheme {
+    pub const fn css(self) -> &'static str {
+        match self {
+            Self::Light => "",
+            Self::Dark => {
+                "body{background:#121212;color:#eee;}
+            main>div{background:#1e1e1e;box-shadow:0 2px 4px rgba(0,0,0,.4);}"
+            }
+            Self::Transparent => {
+                "body{background:transparent;color:#fff;}
+            main>div{background:transparent;box-shadow:none;}
+            main>div p, footer{text-shadow:0 0 4px #000,0 0 2px #000;}"
+            }
+        }
+    }
+}
+
+impl WebsiteLayout {
+    pub const fn css(self) -> &'static str {
+        match self {
+            Self::List => "",
+            Self::Grid => "main{flex-direction:row;flex-wrap:wrap;}",
+            Self::Compact => {
+                "main{gap:.25rem;}
+            main>div{padding:.25rem;gap:.5rem;}
+            main>div img{width:2.5rem;height:2.5rem;}
+            main>div p{font-size:1.5rem;}"
+            }
+        }
+    }
+}
+
+/// Rejects user provided CSS, which could break out of the style element,
+/// load external resources or execute code.
+pub fn validate_custom_css(css: &str) -> Result<String> {
+    if css.chars().count() > CUSTOM_CSS_MAX_LENGTH {
+        return Err(anyhow!(
+            "Custom CSS may be at most {CUSTOM_CSS_MAX_LENGTH} characters long."
+        ));
+    }
+
+    let lowercase = css.to_ascii_lowercase();
+    if let Some(construct) = FORBIDDEN_CSS_CONSTRUCTS
+        .iter()
+        .find(|c| lowercase.contains(*c))
+    {
+        return Err(anyhow!(
+            "Custom CSS may not contain '{construct}', as it could load external resources or run scripts."
+        ));
+    }
+
+    Ok(css.to_owned())
+}
+
+sqlx_text_enum!(WebsiteTheme);
+
+sqlx_text_enum!(WebsiteLayout);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_css_cannot_escape_style_or_load_resources() -> Result<()> {
+        assert_eq!(
+            validate_custom_css("main>div p { color: red; }")?,
+            "main>div p { color: red; }"
+        );
+
+        for forbidden in [
+            "</style><script>alert(1)</script>",
+            "@IMPORT url(x);",
+            "body { background:url(https://tracker/x.png) }",
+            "@font-face { font-family: x; src: local(x) }",
+            "body { background-image: image-set('x.png' 1x) }",
+            "@\\69mport 'x';",
+            "body { background: u\\72l(x) }",
+            "body { background: URL(x) }",
+            "div { width: expression(alert(1)) }",
+        ] {
+            assert!(validate_custom_css(forbidden).is_err(), "{forbidden}");
+        }
+
+        assert!(validate_custom_css(&"a".repeat(CUSTOM_CSS_MAX_LENGTH + 1)).is_err());
+
+        Ok(())
+    }
+}
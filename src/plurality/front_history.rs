@@ -0,0 +1,176 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::database;
+use crate::plurality::Fronter;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrontStart {
+    pub fronter_id: String,
+    pub fronter_name: String,
+    pub start_time: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrontEnd {
+    pub entry_id: i64,
+    pub end_time: DateTime<Utc>,
+}
+
+/// Changes to the front history between the recorded open fronts and the currently fetched fronts.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct FrontTransitions {
+    pub started: Vec<FrontStart>,
+    pub ended: Vec<FrontEnd>,
+}
+
+/// Compares the still open front history entries with the current fronts.
+///
+/// Fronters without a start time from the fronting source are recorded as starting `now`.
+/// If a fronter started fronting again since the open entry was recorded
+/// (e.g. a switch happened while the updater wasn't running), the old entry
+/// ends when the new front started.
+pub fn front_transitions(
+    open_entries: &[database::FrontHistoryEntry],
+    fronts: &[Fronter],
+    now: DateTime<Utc>,
+) -> FrontTransitions {
+    let start_time_of = |f: &Fronter| f.start_time.map_or(now, |t| t.min(now));
+
+    let mut transitions = FrontTransitions::default();
+    let mut still_fronting = vec![];
+
+    for entry in open_entries {
+        match fronts.iter().find(|f| f.id == entry.fronter_id) {
+            None => transitions.ended.push(FrontEnd {
+                entry_id: entry.id,
+                end_time: now,
+            }),
+            Some(f) if f.start_time.is_some_and(|t| t.min(now) > entry.start_time) => {
+                transitions.ended.push(FrontEnd {
+                    entry_id: entry.id,
+                    end_time: start_time_of(f),
+                });
+            }
+            Some(f) => still_fronting.push(f.id.clone()),
+        }
+    }
+
+    transitions.started = fronts
+        .iter()
+        .filter(|f| !still_fronting.contains(&f.id))
+        .map(|f| FrontStart {
+            fronter_id: f.id.clone(),
+            fronter_name: f.name.clone(),
+            start_time: start_time_of(f),
+        })
+        .collect();
+
+    transitions
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FrontingStats {
+    pub from: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    /// Sorted by the time fronted, longest first.
+    pub members: Vec<MemberFrontingTime>,
+    /// Number of fronts started within the period.
+    pub switch_count: usize,
+    pub longest_co_front: Option<CoFront>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct MemberFrontingTime {
+    pub fronter_id: String,
+    pub fronter_name: String,
+    pub seconds_fronted: i64,
+}
+
+/// The longest time two fronters were fronting at the same time.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct CoFront {
+    pub fronter_names: Vec<String>,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub seconds: i64,
+}
+
+/// Statistics over the front history entries within `from` until `until`.
+/// Entries are clipped to that range and entries still open count as fronting until `until`.
+pub fn fronting_stats(
+    entries: &[database::FrontHistoryEntry],
+    from: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> FrontingStats {
+    let clipped: Vec<(&database::FrontHistoryEntry, DateTime<Utc>, DateTime<Utc>)> = entries
+        .iter()
+        .map(|e| {
+            let start = e.start_time.max(from);
+            let end = e.end_time.unwrap_or(until).min(until);
+            (e, start, end)
+        })
+        .filter(|(_, start, end)| start < end)
+        .collect();
+
+    let mut members: HashMap<&str, MemberFrontingTime> = HashMap::new();
+    for (entry, start, end) in &clipped {
+        let member = members
+            .entry(&entry.fronter_id)
+            .or_insert_with(|| MemberFrontingTime {
+                fronter_id: entry.fronter_id.clone(),
+                fronter_name: entry.fronter_name.clone(),
+                seconds_fronted: 0,
+            });
+        // the most recent name wins, in case the member was renamed
+        member.fronter_name.clone_from(&entry.fronter_name);
+        member.seconds_fronted += (*end - *start).num_seconds();
+    }
+    let mut members: Vec<MemberFrontingTime> = members.into_values().collect();
+    members.sort_by(|a, b| {
+        b.seconds_fronted
+            .cmp(&a.seconds_fronted)
+            .then_with(|| a.fronter_name.cmp(&b.fronter_name))
+    });
+
+    let switch_count = clipped
+        .iter()
+        .filter(|(e, _, _)| e.start_time >= from)
+        .count();
+
+    FrontingStats {
+        from,
+        until,
+        members,
+        switch_count,
+        longest_co_front: longest_co_front(&clipped),
+    }
+}
+
+fn longest_co_front(
+    clipped: &[(&database::FrontHistoryEntry, DateTime<Utc>, DateTime<Utc>)],
+) -> Option<CoFront> {
+    let mut longest: Option<CoFront> = None;
+
+    for (i, (a, a_start, a_end)) in clipped.iter().enumerate() {
+        for (b, b_start, b_end) in &clipped[i + 1..] {
+            if a.fronter_id == b.fronter_id {
+                continue;
+            }
+            let start = *a_start.max(b_start);
+            let end = *a_end.min(b_end);
+            let seconds = (end - start).num_seconds();
+            if seconds > 0 && longest.as_ref().is_none_or(|l| seconds > l.seconds) {
+                longest = Some(CoFront {
+                    fronter_names: vec![a.fronter_name.clone(), b.fronter_name.clone()],
+                    start_time: start,
+                    end_time: end,
+                    seconds,
+                });
+            }
+        }
+    }
+
+    longest
+}
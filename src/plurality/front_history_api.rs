@@ -0,0 +1,80 @@
+use crate::database;
+use crate::http;
+use crate::http::HttpResult;
+use crate::plurality::{self, FrontingStats};
+use crate::users;
+use chrono::{Duration, Utc};
+use rocket::{serde::json::Json, State};
+use serde::Serialize;
+use sqlx::PgPool;
+
+#[derive(FromFormField, Clone, Copy, Default)]
+pub enum StatsPeriod {
+    #[field(value = "day")]
+    Day,
+    #[default]
+    #[field(value = "week")]
+    Week,
+    #[field(value = "month")]
+    Month,
+}
+
+impl StatsPeriod {
+    const fn duration(self) -> Duration {
+        match self {
+            Self::Day => Duration::days(1),
+            Self::Week => Duration::weeks(1),
+            Self::Month => Duration::days(30),
+        }
+    }
+}
+
+#[get("/api/fronting/history?<page>&<page_size>")]
+pub async fn get_api_fronting_history(
+    jwt: HttpResult<users::Jwt>,
+    db_pool: &State<PgPool>,
+    page: Option<u32>,
+    page_size: Option<u32>,
+) -> HttpResult<Json<FrontHistoryPage>> {
+    let user_id = jwt?.user_id()?;
+
+    let pagination = http::Pagination::new(page, page_size);
+
+    let entries = database::get_front_history_entries(
+        db_pool,
+        &user_id,
+        pagination.limit(),
+        pagination.offset(),
+    )
+    .await?;
+
+    Ok(Json(FrontHistoryPage {
+        page: pagination.page,
+        page_size: pagination.page_size,
+        entries,
+    }))
+}
+
+#[derive(Serialize)]
+pub struct FrontHistoryPage {
+    pub page: u32,
+    pub page_size: u32,
+    pub entries: Vec<database::FrontHistoryEntry>,
+}
+
+#[get("/api/fronting/stats?<period>")]
+pub async fn get_api_fronting_stats(
+    jwt: HttpResult<users::Jwt>,
+    db_pool: &State<PgPool>,
+    period: Option<StatsPeriod>,
+) -> HttpResult<Json<FrontingStats>> {
+    let user_id = jwt?.user_id()?;
+
+    let until = Utc::now();
+    let from = until - period.unwrap_or_default().duration();
+
+    let entries =
+        database::get_front_history_entries_between(db_pool, &user_id, from, until).await?;
+
+    Ok(Json(plurality::fronting_stats(&entries, from, until)))
+}
@@ -0,0 +1,131 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+use crate::database::FrontHistoryEntry;
+use crate::plurality::{front_transitions, fronting_stats, FrontEnd, FrontStart, Fronter};
+
+fn at(hour: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 1, 1, hour, 0, 0)
+        .single()
+        .unwrap_or_default()
+}
+
+fn entry(
+    id: i64,
+    fronter_id: &str,
+    start_time: DateTime<Utc>,
+    end_time: Option<DateTime<Utc>>,
+) -> FrontHistoryEntry {
+    FrontHistoryEntry {
+        id,
+        fronter_id: fronter_id.to_owned(),
+        fronter_name: format!("Name of {fronter_id}"),
+        start_time,
+        end_time,
+    }
+}
+
+#[test]
+fn test_front_transitions_start_new_and_end_missing_fronters() {
+    let open = vec![entry(1, "a", at(1), None), entry(2, "b", at(1), None)];
+    let fronts = vec![
+        Fronter::for_tests("Name of a")
+            .with_id("a")
+            .with_start_time(at(1)),
+        Fronter::for_tests("Name of c").with_id("c"),
+    ];
+
+    let transitions = front_transitions(&open, &fronts, at(5));
+
+    assert_eq!(
+        transitions.ended,
+        vec![FrontEnd {
+            entry_id: 2,
+            end_time: at(5)
+        }]
+    );
+    assert_eq!(
+        transitions.started,
+        vec![FrontStart {
+            fronter_id: String::from("c"),
+            fronter_name: String::from("Name of c"),
+            start_time: at(5),
+        }]
+    );
+}
+
+#[test]
+fn test_front_transitions_refront_since_last_check_ends_old_entry() {
+    let open = vec![entry(1, "a", at(1), None)];
+    let fronts = vec![Fronter::for_tests("Name of a")
+        .with_id("a")
+        .with_start_time(at(3))];
+
+    let transitions = front_transitions(&open, &fronts, at(5));
+
+    assert_eq!(
+        transitions.ended,
+        vec![FrontEnd {
+            entry_id: 1,
+            end_time: at(3)
+        }]
+    );
+    assert_eq!(transitions.started.len(), 1);
+    assert_eq!(transitions.started[0].start_time, at(3));
+}
+
+#[test]
+fn test_front_transitions_unchanged_fronts_have_no_transitions() {
+    let open = vec![entry(1, "a", at(1), None)];
+    let fronts = vec![Fronter::for_tests("Name of a")
+        .with_id("a")
+        .with_start_time(at(1))];
+
+    let transitions = front_transitions(&open, &fronts, at(5));
+
+    assert!(transitions.started.is_empty());
+    assert!(transitions.ended.is_empty());
+}
+
+#[test]
+fn test_fronting_stats_clips_to_period_and_finds_longest_co_front() {
+    let entries = vec![
+        // started before the period
+        entry(1, "a", at(0), Some(at(4))),
+        // still fronting
+        entry(2, "b", at(3), None),
+        entry(3, "c", at(5), Some(at(7))),
+        entry(4, "a", at(8), Some(at(9))),
+    ];
+
+    let stats = fronting_stats(&entries, at(2), at(10));
+
+    let seconds_of = |id: &str| {
+        stats
+            .members
+            .iter()
+            .find(|m| m.fronter_id == id)
+            .map(|m| m.seconds_fronted)
+    };
+    assert_eq!(seconds_of("a"), Some(Duration::hours(3).num_seconds()));
+    assert_eq!(seconds_of("b"), Some(Duration::hours(7).num_seconds()));
+    assert_eq!(seconds_of("c"), Some(Duration::hours(2).num_seconds()));
+    assert_eq!(stats.members[0].fronter_id, "b");
+
+    assert_eq!(stats.switch_count, 3);
+
+    let co_front = stats
+        .longest_co_front
+        .unwrap_or_else(|| panic!("no co-front"));
+    assert_eq!(co_front.start_time, at(5));
+    assert_eq!(co_front.end_time, at(7));
+    assert_eq!(co_front.fronter_names, vec!["Name of b", "Name of c"]);
+}
+
+#[test]
+fn test_fronting_stats_without_history_is_empty() {
+    let stats = fronting_stats(&[], at(2), at(10));
+
+    assert!(stats.members.is_empty());
+    assert_eq!(stats.switch_count, 0);
+    assert!(stats.longest_co_front.is_none());
+}
@@ -0,0 +1,35 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    plurality::{self, Fronter},
+    sqlx_text_enum, users,
+};
+
+/// The plural system tracker from which the current fronters are fetched.
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Serialize,
+    Deserialize,
+    strum_macros::Display,
+    strum_macros::EnumString,
+    PartialEq,
+    Eq,
+    Hash,
+)]
+pub enum FrontingSource {
+    #[default]
+    SimplyPlural,
+    PluralKit,
+}
+
+pub async fn fetch_fronts(config: &users::UserConfigForUpdater) -> Result<Vec<Fronter>> {
+    match config.fronting_source {
+        FrontingSource::SimplyPlural => plurality::fetch_simply_plural_fronts(config).await,
+        FrontingSource::PluralKit => plurality::fetch_pluralkit_fronts(config).await,
+    }
+}
+
+sqlx_text_enum!(FrontingSource);
@@ -1,16 +1,25 @@
-use crate::plurality::Fronter;
+use crate::plurality::{Fronter, StatusTemplate, TemplateValues};
 
 use encoding_rs::ISO_8859_15;
+use unicode_segmentation::UnicodeSegmentation;
 
 pub const VRCHAT_MAX_ALLOWED_STATUS_LENGTH: usize = 23;
 pub const DISCORD_STATUS_MAX_LENGTH: usize = 128;
 
+// VRChat status names are cleaned to single code unit characters anyways.
+// Discord counts the length of strings in UTF-16 code units like JavaScript does.
+pub const VRCHAT_STATUS_LENGTH_MEASURE: LengthMeasure = LengthMeasure::Chars;
+pub const DISCORD_STATUS_LENGTH_MEASURE: LengthMeasure = LengthMeasure::Utf16CodeUnits;
+
 pub struct FrontingFormat {
     pub max_length: Option<usize>,
+    pub length_measure: LengthMeasure,
     pub cleaning: CleanForPlatform,
     pub prefix: String,
     pub status_if_no_fronters: String,
     pub truncate_names_to_length_if_status_too_long: usize,
+    // ordered by preference. the first one within the max length is used.
+    pub templates: Vec<StatusTemplate>,
 }
 
 pub enum CleanForPlatform {
@@ -18,17 +27,49 @@ pub enum CleanForPlatform {
     VRChat,
 }
 
+/// How a platform counts the length of a status string.
+#[derive(Clone, Copy, Debug)]
+pub enum LengthMeasure {
+    Chars,
+    Utf16CodeUnits,
+    Graphemes,
+}
+
+impl LengthMeasure {
+    pub fn length(self, s: &str) -> usize {
+        match self {
+            Self::Chars => s.chars().count(),
+            Self::Utf16CodeUnits => s.encode_utf16().count(),
+            Self::Graphemes => s.graphemes(true).count(),
+        }
+    }
+}
+
 pub fn format_fronting_status(fronting_format: &FrontingFormat, fronts: &[Fronter]) -> String {
     let cleaned_fronter_names = collect_clean_fronter_names(fronting_format, fronts);
     eprintln!("Cleaned fronter names for status: {cleaned_fronter_names:?}");
 
-    let status_strings =
-        compute_status_strings_of_decreasing_lengths_for_aesthetics_and_information_tradeoff(
-            fronting_format,
-            &cleaned_fronter_names,
-        );
+    let values = TemplateValues {
+        prefix: &fronting_format.prefix,
+        names: &cleaned_fronter_names,
+        count: fronts.len(),
+        truncate_names_to: fronting_format.truncate_names_to_length_if_status_too_long,
+    };
 
-    pick_longest_string_within_vrchat_status_length_limit(fronting_format, &status_strings)
+    let status_strings: Vec<String> = fronting_format
+        .templates
+        .iter()
+        .map(|template| template.render(&values))
+        .inspect(|s| {
+            eprintln!(
+                "Status candidate: '{}' ({})",
+                s,
+                fronting_format.length_measure.length(s)
+            );
+        })
+        .collect();
+
+    pick_first_string_within_status_length_limit(fronting_format, &status_strings)
 }
 
 fn collect_clean_fronter_names(
@@ -50,81 +91,46 @@ fn collect_clean_fronter_names(
     }
 }
 
-fn compute_status_strings_of_decreasing_lengths_for_aesthetics_and_information_tradeoff(
+fn pick_first_string_within_status_length_limit(
     fronting_format: &FrontingFormat,
-    fronter_names: &[String],
-) -> Vec<String> {
-    // Convert Vec<String> to Vec<&str> for convenient joining and slicing.
-    let fronter_names_as_str: Vec<&str> = fronter_names.iter().map(String::as_str).collect();
-
-    let long_string = format!(
-        "{} {}",
-        fronting_format.prefix,
-        fronter_names_as_str.join(", ")
-    );
-    let short_string = format!(
-        "{}{}",
-        fronting_format.prefix,
-        fronter_names_as_str.join(",")
-    );
-    let truncated_string = {
-        let truncated_names: Vec<String> = fronter_names_as_str
-            .iter()
-            .map(|&name| {
-                let mut truncated_name = String::new();
-
-                let () = &name
-                    .chars()
-                    .take(fronting_format.truncate_names_to_length_if_status_too_long)
-                    .for_each(|c| truncated_name.push(c));
-
-                truncated_name
-            })
-            .collect();
-        format!(
-            "{}{}",
-            fronting_format.prefix,
-            truncated_names.join(",").as_str()
-        )
+    status_strings: &[String],
+) -> String {
+    let measure = fronting_format.length_measure;
+    let within_limit = |s: &String| {
+        fronting_format
+            .max_length
+            .is_none_or(|l| measure.length(s) <= l)
     };
-    let count_string = format!("{} {}#", fronting_format.prefix, fronter_names.len());
-
-    eprintln!(
-        "Long      string: '{}' ({})",
-        long_string,
-        long_string.len()
-    );
-    eprintln!(
-        "Short     string: '{}' ({})",
-        short_string,
-        short_string.len()
-    );
-    eprintln!(
-        "Truncated string: '{}' ({})",
-        truncated_string,
-        truncated_string.len()
-    );
-
-    eprintln!(
-        "Count     string: '{}' ({})",
-        count_string,
-        count_string.len()
-    );
-
-    vec![long_string, short_string, truncated_string, count_string]
+
+    status_strings.iter().find(|s| within_limit(s)).map_or_else(
+        || {
+            // no template fits. use the shortest one cut to the limit.
+            let shortest = status_strings
+                .iter()
+                .min_by_key(|s| measure.length(s))
+                .cloned()
+                .unwrap_or_default();
+            fronting_format.max_length.map_or_else(
+                || shortest.clone(),
+                |l| truncate_to_length(&shortest, l, measure),
+            )
+        },
+        Clone::clone,
+    )
 }
 
-fn pick_longest_string_within_vrchat_status_length_limit(
-    fronting_format: &FrontingFormat,
-    status_strings: &[String],
-) -> String {
-    let empty_string = String::new();
-    status_strings
-        .iter()
-        .filter(|s| fronting_format.max_length.is_none_or(|l| s.len() <= l))
-        .max_by_key(|s| s.len())
-        .unwrap_or(&empty_string) // can't happen due to compile time guarantee
-        .to_string()
+/// Truncates the string to the maximum length without splitting any grapheme.
+pub fn truncate_to_length(s: &str, max_length: usize, measure: LengthMeasure) -> String {
+    let mut truncated = String::new();
+
+    for grapheme in s.graphemes(true) {
+        if measure.length(&truncated) + measure.length(grapheme) > max_length {
+            break;
+        }
+        truncated.push_str(grapheme);
+    }
+
+    truncated
 }
 
 // VRChat status messages does not display all UTF-8 characters.
@@ -1,8 +1,13 @@
 use crate::plurality::{
-    clean_name_for_vrchat_status, format_fronting_status, CleanForPlatform, Fronter,
-    FrontingFormat, VRCHAT_MAX_ALLOWED_STATUS_LENGTH,
+    clean_name_for_vrchat_status, format_fronting_status, parse_status_templates,
+    truncate_to_length, CleanForPlatform, Fronter, FrontingFormat, LengthMeasure, StatusTemplate,
+    DEFAULT_STATUS_TEMPLATES, DISCORD_STATUS_LENGTH_MEASURE, VRCHAT_MAX_ALLOWED_STATUS_LENGTH,
+    VRCHAT_STATUS_LENGTH_MEASURE,
 };
+use proptest::prelude::*;
+use unicode_segmentation::UnicodeSegmentation;
 
+#[allow(clippy::unwrap_used)]
 fn mock_formatter_for_tests(
     prefix: &str,
     no_fronts: &str,
@@ -15,6 +20,8 @@ fn mock_formatter_for_tests(
         truncate_names_to_length_if_status_too_long: name_truncate_to,
         cleaning: CleanForPlatform::VRChat,
         max_length: Some(max_length),
+        length_measure: VRCHAT_STATUS_LENGTH_MEASURE,
+        templates: parse_status_templates(&DEFAULT_STATUS_TEMPLATES).unwrap(),
     }
 }
 
@@ -29,6 +36,9 @@ fn mock_member_content(name: &str, vrchat_status_name: &str) -> Fronter {
         } else {
             Some(vrchat_status_name.to_owned())
         },
+        start_time: None,
+        private: false,
+        color: None,
     }
 }
 
@@ -178,3 +188,164 @@ fn test_clean_name_for_vrchat_encoding_and_whitespace() {
         "Should collapse spaces created by invalid characters"
     );
 }
+
+#[allow(clippy::unwrap_used)]
+fn mock_formatter_with_templates(templates: &[&str], max_length: usize) -> FrontingFormat {
+    FrontingFormat {
+        templates: parse_status_templates(templates).unwrap(),
+        ..mock_formatter_for_tests("F:", "N/A", 3, max_length)
+    }
+}
+
+#[test]
+fn test_format_status_with_custom_template() {
+    let config = mock_formatter_with_templates(
+        &[r#"{prefix} {names|join:" & "} ({count})"#],
+        VRCHAT_MAX_ALLOWED_STATUS_LENGTH,
+    );
+    let fronts = vec![
+        mock_member_content("Alice", ""),
+        mock_member_content("Bob", ""),
+    ];
+    assert_eq!(
+        format_fronting_status(&config, &fronts),
+        "F: Alice & Bob (2)"
+    );
+}
+
+#[test]
+fn test_format_status_picks_first_fitting_template() {
+    let config = mock_formatter_with_templates(
+        &[
+            "Fronting: {names}",
+            "{names|truncate:2|join:\"|\"}",
+            "{count} fronting",
+        ],
+        10,
+    );
+    let fronts = vec![
+        mock_member_content("Alice", ""),
+        mock_member_content("Bob", ""),
+    ];
+    // "Fronting: Alice, Bob" 20 > 10
+    // "Al|Bo" 5 <= 10
+    assert_eq!(format_fronting_status(&config, &fronts), "Al|Bo");
+}
+
+#[test]
+fn test_format_status_cuts_shortest_template_if_nothing_fits() {
+    let config = mock_formatter_with_templates(&["{names}", "Fronting: {names}"], 8);
+    let fronts = vec![mock_member_content("Alexander", "")];
+    assert_eq!(format_fronting_status(&config, &fronts), "Alexande");
+}
+
+#[test]
+fn test_parse_status_template_errors() {
+    assert!(StatusTemplate::parse("{prefix").is_err());
+    assert!(StatusTemplate::parse("prefix}").is_err());
+    assert!(StatusTemplate::parse("{unknown}").is_err());
+    assert!(StatusTemplate::parse("{names|shout}").is_err());
+    assert!(StatusTemplate::parse("{names|truncate:many}").is_err());
+    assert!(StatusTemplate::parse("{names|join:,}").is_err());
+    assert!(StatusTemplate::parse("{count|truncate}").is_err());
+    assert!(parse_status_templates::<&str>(&[]).is_err());
+
+    assert!(StatusTemplate::parse(r#"{names|join:"}|{"}"#).is_ok());
+    assert!(StatusTemplate::parse("no placeholders").is_ok());
+}
+
+#[test]
+fn test_format_vrchat_status_counts_characters_not_bytes() {
+    let config = mock_formatter_for_tests("F:", "N/A", 3, VRCHAT_MAX_ALLOWED_STATUS_LENGTH);
+    let fronts = vec![
+        mock_member_content("Héllo Wörld", ""),
+        mock_member_content("Ärger", ""),
+    ];
+    // "F: Héllo Wörld, Ärger" 21 chars (24 bytes) <= 23
+    assert_eq!(
+        format_fronting_status(&config, &fronts),
+        "F: Héllo Wörld, Ärger"
+    );
+}
+
+#[test]
+fn test_format_discord_status_counts_utf16_code_units() {
+    let config = FrontingFormat {
+        cleaning: CleanForPlatform::NoClean,
+        length_measure: DISCORD_STATUS_LENGTH_MEASURE,
+        ..mock_formatter_with_templates(&["{names}", "{count}"], 6)
+    };
+    // "😊😊😊" has 3 chars, but 6 UTF-16 code units
+    let fronts = vec![mock_member_content("😊😊😊", "")];
+    assert_eq!(format_fronting_status(&config, &fronts), "😊😊😊");
+
+    let fronts = vec![mock_member_content("😊😊😊😊", "")];
+    assert_eq!(format_fronting_status(&config, &fronts), "1");
+}
+
+#[test]
+fn test_truncate_to_length_keeps_graphemes_intact() {
+    let family = "👨‍👩‍👧"; // 1 grapheme, 5 chars, 8 UTF-16 code units
+
+    assert_eq!(
+        truncate_to_length(&format!("ab{family}"), 5, LengthMeasure::Utf16CodeUnits),
+        "ab"
+    );
+    assert_eq!(
+        truncate_to_length(&format!("ab{family}"), 5, LengthMeasure::Chars),
+        "ab"
+    );
+    assert_eq!(
+        truncate_to_length(&format!("ab{family}c"), 3, LengthMeasure::Graphemes),
+        format!("ab{family}")
+    );
+    assert_eq!(
+        truncate_to_length("e\u{301}e\u{301}", 3, LengthMeasure::Chars),
+        "e\u{301}"
+    );
+}
+
+fn any_length_measure() -> impl Strategy<Value = LengthMeasure> {
+    prop_oneof![
+        Just(LengthMeasure::Chars),
+        Just(LengthMeasure::Utf16CodeUnits),
+        Just(LengthMeasure::Graphemes),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn prop_formatted_status_never_exceeds_max_length(
+        names in prop::collection::vec("\\PC{0,20}", 0..6),
+        prefix in "\\PC{0,5}",
+        max_length in 0usize..40,
+        length_measure in any_length_measure(),
+        clean_for_vrchat in any::<bool>(),
+    ) {
+        let config = FrontingFormat {
+            prefix,
+            length_measure,
+            cleaning: if clean_for_vrchat { CleanForPlatform::VRChat } else { CleanForPlatform::NoClean },
+            ..mock_formatter_for_tests("", "N/A", 3, max_length)
+        };
+        let fronts: Vec<Fronter> = names.iter().map(|n| mock_member_content(n, "")).collect();
+
+        let status = format_fronting_status(&config, &fronts);
+
+        prop_assert!(length_measure.length(&status) <= max_length);
+    }
+
+    #[test]
+    fn prop_truncation_never_exceeds_max_length_nor_splits_graphemes(
+        s in "\\PC{0,30}",
+        max_length in 0usize..40,
+        length_measure in any_length_measure(),
+    ) {
+        let truncated = truncate_to_length(&s, max_length, length_measure);
+
+        prop_assert!(length_measure.length(&truncated) <= max_length);
+
+        let n_graphemes = truncated.graphemes(true).count();
+        prop_assert_eq!(s.graphemes(true).take(n_graphemes).collect::<String>(), truncated);
+    }
+}
@@ -1,11 +1,35 @@
+mod front_history;
+pub mod front_history_api;
+
+#[cfg(test)]
+mod front_history_tests;
+
 pub mod fronting_status;
+mod status_template;
 
 #[cfg(test)]
 mod fronting_status_tests;
 
+mod fronting_source;
+mod pluralkit;
+mod pluralkit_model;
+
+#[cfg(test)]
+mod pluralkit_tests;
+
 mod simply_plural;
 mod simply_plural_model;
+mod simply_plural_websocket;
+
+#[cfg(test)]
+mod simply_plural_websocket_tests;
 
+pub use front_history::*;
+pub use fronting_source::*;
 pub use fronting_status::*;
+pub use pluralkit::*;
+pub use pluralkit_model::*;
 pub use simply_plural::*;
 pub use simply_plural_model::*;
+pub use simply_plural_websocket::*;
+pub use status_template::*;
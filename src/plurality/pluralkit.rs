@@ -0,0 +1,81 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+
+use crate::{
+    plurality::{Fronter, PluralKitSwitch, PluralKitSwitchEntry},
+    users,
+};
+
+// the maximum page size of PluralKit. fronts lasting more switches start at the oldest of them.
+const PLURALKIT_SWITCHES_LIMIT: u32 = 100;
+
+pub async fn fetch_pluralkit_fronts(config: &users::UserConfigForUpdater) -> Result<Vec<Fronter>> {
+    eprintln!("Fetching fronts from PluralKit...");
+    let fronters_url = format!("{}/systems/@me/fronters", &config.pluralkit_base_url);
+    let response = config
+        .client
+        .get(&fronters_url)
+        .header("Authorization", &config.pluralkit_token.secret)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    // PluralKit responds without content, if the system has never registered a switch.
+    if response.status() == StatusCode::NO_CONTENT {
+        return Ok(vec![]);
+    }
+
+    let switch: PluralKitSwitch = response.json().await?;
+
+    // members, who stay in front across switches, keep fronting since the earliest of those switches
+    let switches = if switch.members.is_empty() {
+        vec![]
+    } else {
+        fetch_pluralkit_switches(config).await?
+    };
+
+    let fronters: Vec<Fronter> = switch
+        .members
+        .iter()
+        .map(|m| Fronter {
+            start_time: Some(front_start_time(&switches, &m.id).unwrap_or(switch.timestamp)),
+            ..Fronter::from(m)
+        })
+        .collect();
+
+    for f in &fronters {
+        eprintln!("Fronter: {f:?}");
+    }
+
+    Ok(fronters)
+}
+
+/// The most recent switches, newest first.
+async fn fetch_pluralkit_switches(
+    config: &users::UserConfigForUpdater,
+) -> Result<Vec<PluralKitSwitchEntry>> {
+    let switches_url = format!(
+        "{}/systems/@me/switches?limit={PLURALKIT_SWITCHES_LIMIT}",
+        &config.pluralkit_base_url
+    );
+
+    Ok(config
+        .client
+        .get(&switches_url)
+        .header("Authorization", &config.pluralkit_token.secret)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?)
+}
+
+// the oldest of the consecutive switches up to the newest one, which all contain the member
+fn front_start_time(switches: &[PluralKitSwitchEntry], member_id: &str) -> Option<DateTime<Utc>> {
+    switches
+        .iter()
+        .take_while(|s| s.members.iter().any(|id| id == member_id))
+        .last()
+        .map(|s| s.timestamp)
+}
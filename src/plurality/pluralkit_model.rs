@@ -0,0 +1,58 @@
+use serde::Deserialize;
+
+use crate::plurality::{normalize_hex_color, Fronter};
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PluralKitSwitch {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub members: Vec<PluralKitMember>,
+}
+
+/// A switch from the list of switches, which only references the members by their ID.
+#[derive(Deserialize, Debug, Clone)]
+pub struct PluralKitSwitchEntry {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub members: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PluralKitMember {
+    pub id: String, // short 5/6 letter member id
+    pub name: String,
+
+    #[serde(default)]
+    pub display_name: Option<String>,
+
+    #[serde(default)]
+    pub avatar_url: Option<String>,
+
+    // hex color without leading '#'
+    #[serde(default)]
+    pub color: Option<String>,
+
+    // only present, when the token belongs to the system of the member
+    #[serde(default)]
+    pub privacy: Option<PluralKitMemberPrivacy>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PluralKitMemberPrivacy {
+    #[serde(default)]
+    pub visibility: Option<String>,
+}
+
+impl From<&PluralKitMember> for Fronter {
+    fn from(m: &PluralKitMember) -> Self {
+        Self {
+            id: m.id.clone(),
+            name: m.name.clone(),
+            avatar_url: m.avatar_url.clone().unwrap_or_default(),
+            // the display name plays the same role as the "VRChat Status Name" in SimplyPlural
+            vrchat_status_name: m.display_name.clone().filter(|n| !n.is_empty()),
+            // PluralKit only knows when the switch happened. set from the switch.
+            start_time: None,
+            private: m.privacy.as_ref().and_then(|p| p.visibility.as_deref()) == Some("private"),
+            color: m.color.as_deref().and_then(normalize_hex_color),
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use crate::database::Decrypted;
+use crate::plurality::{fetch_fronts, FrontingSource};
+use crate::users::{self, UserConfigDbEntries};
+use anyhow::Result;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+const PLURALKIT_TOKEN: &str = "pk_token_123";
+
+// Serves one HTTP request per given response, in order, and returns the received requests.
+async fn serve_responses_locally(
+    responses: Vec<(&'static str, &'static str)>,
+) -> Result<(String, tokio::task::JoinHandle<Vec<String>>)> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let base_url = format!("http://{}", listener.local_addr()?);
+
+    let server = tokio::spawn(async move {
+        let mut requests = vec![];
+
+        for (status_line, body) in responses {
+            let Ok((mut stream, _)) = listener.accept().await else {
+                break;
+            };
+
+            let mut request = vec![0u8; 4096];
+            let n = stream.read(&mut request).await.unwrap_or_default();
+
+            let response = format!(
+                "HTTP/1.1 {status_line}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+
+            requests.push(String::from_utf8_lossy(&request[..n]).to_string());
+        }
+
+        requests
+    });
+
+    Ok((base_url, server))
+}
+
+fn pluralkit_config_for_tests(base_url: String) -> Result<users::UserConfigForUpdater> {
+    let db_config = UserConfigDbEntries::<Decrypted> {
+        system_name: Some("PK System".to_owned()),
+        fronting_source: Some(FrontingSource::PluralKit),
+        pluralkit_token: Some(PLURALKIT_TOKEN.into()),
+        ..Default::default()
+    };
+
+    let mut config = users::config_for_tests(&db_config)?;
+
+    config.pluralkit_base_url = base_url;
+
+    Ok(config)
+}
+
+#[tokio::test]
+async fn test_pluralkit_fronters_are_mapped_to_fronters() -> Result<()> {
+    let body = r#"{
+        "id": "a1b2c3",
+        "timestamp": "2025-01-01T12:00:00Z",
+        "members": [
+            { "id": "abcde", "name": "Alice", "display_name": "Ally", "avatar_url": "https://example.com/a", "color": "FF00aa" },
+            { "id": "fghij", "name": "Bob", "display_name": null, "avatar_url": null, "privacy": { "visibility": "private" } }
+        ]
+    }"#;
+    let switches = r#"[
+        { "id": "a1b2c3", "timestamp": "2025-01-01T12:00:00Z", "members": ["abcde", "fghij"] }
+    ]"#;
+    let (base_url, server) =
+        serve_responses_locally(vec![("200 OK", body), ("200 OK", switches)]).await?;
+    let config = pluralkit_config_for_tests(base_url)?;
+
+    let fronters = fetch_fronts(&config).await?;
+    let requests = server.await?;
+
+    assert!(requests[0].starts_with("GET /systems/@me/fronters HTTP/1.1"));
+    assert!(requests[1].starts_with("GET /systems/@me/switches?limit=100 HTTP/1.1"));
+    assert!(requests.iter().all(|r| r
+        .to_lowercase()
+        .contains(&format!("authorization: {PLURALKIT_TOKEN}"))));
+
+    assert_eq!(fronters.len(), 2);
+    assert_eq!(fronters[0].id, "abcde");
+    assert_eq!(fronters[0].name, "Alice");
+    assert_eq!(fronters[0].avatar_url, "https://example.com/a");
+    assert_eq!(fronters[0].preferred_vrchat_status_name(), "Ally");
+    assert_eq!(fronters[1].avatar_url, "");
+    assert_eq!(fronters[1].preferred_vrchat_status_name(), "Bob");
+    assert!(!fronters[0].private);
+    assert_eq!(fronters[0].color.as_deref(), Some("#ff00aa"));
+    assert_eq!(fronters[1].color, None);
+    assert!(fronters[1].private);
+    assert_eq!(
+        fronters[0].start_time.map(|t| t.to_rfc3339()),
+        Some(String::from("2025-01-01T12:00:00+00:00"))
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pluralkit_members_staying_in_front_keep_their_start_time() -> Result<()> {
+    let body = r#"{
+        "id": "s3",
+        "timestamp": "2025-01-01T14:00:00Z",
+        "members": [
+            { "id": "abcde", "name": "Alice" },
+            { "id": "fghij", "name": "Bob" }
+        ]
+    }"#;
+    // Alice fronted since the first switch. Bob joined with the latest one.
+    let switches = r#"[
+        { "id": "s3", "timestamp": "2025-01-01T14:00:00Z", "members": ["abcde", "fghij"] },
+        { "id": "s2", "timestamp": "2025-01-01T13:00:00Z", "members": ["abcde"] },
+        { "id": "s1", "timestamp": "2025-01-01T12:00:00Z", "members": ["fghij", "abcde"] },
+        { "id": "s0", "timestamp": "2025-01-01T11:00:00Z", "members": ["fghij"] }
+    ]"#;
+    let (base_url, server) =
+        serve_responses_locally(vec![("200 OK", body), ("200 OK", switches)]).await?;
+    let config = pluralkit_config_for_tests(base_url)?;
+
+    let fronters = fetch_fronts(&config).await?;
+    let _ = server.await?;
+
+    let start_times: Vec<Option<String>> = fronters
+        .iter()
+        .map(|f| f.start_time.map(|t| t.to_rfc3339()))
+        .collect();
+    assert_eq!(
+        start_times,
+        vec![
+            Some(String::from("2025-01-01T12:00:00+00:00")),
+            Some(String::from("2025-01-01T14:00:00+00:00")),
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pluralkit_without_any_switch_has_no_fronters() -> Result<()> {
+    let (base_url, server) = serve_responses_locally(vec![("204 No Content", "")]).await?;
+    let config = pluralkit_config_for_tests(base_url)?;
+
+    let fronters = fetch_fronts(&config).await?;
+    let _ = server.await?;
+
+    assert!(fronters.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pluralkit_invalid_token_is_an_error() -> Result<()> {
+    let body = r#"{ "code": 0, "message": "401: Missing or invalid Authorization header" }"#;
+    let (base_url, server) = serve_responses_locally(vec![("401 Unauthorized", body)]).await?;
+    let config = pluralkit_config_for_tests(base_url)?;
+
+    let result = fetch_fronts(&config).await;
+    let _ = server.await?;
+
+    assert!(result.is_err());
+
+    Ok(())
+}
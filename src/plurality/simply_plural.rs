@@ -5,7 +5,9 @@ use crate::{
     users,
 };
 
-pub async fn fetch_fronts(config: &users::UserConfigForUpdater) -> Result<Vec<Fronter>> {
+pub async fn fetch_simply_plural_fronts(
+    config: &users::UserConfigForUpdater,
+) -> Result<Vec<Fronter>> {
     let front_entries = simply_plural_http_request_get_fronters(config).await?;
 
     if front_entries.is_empty() {
@@ -63,14 +65,15 @@ fn filter_frontables_by_front_entries(
     front_entries: Vec<FrontEntry>,
     frontables: Vec<Fronter>,
 ) -> Vec<Fronter> {
-    let fronter_ids: Vec<String> = front_entries
-        .iter()
-        .map(|e| e.content.member.clone())
-        .collect();
-
     let fronters: Vec<Fronter> = frontables
         .into_iter()
-        .filter(|f| fronter_ids.contains(&f.id))
+        .filter_map(|f| {
+            let entry = front_entries.iter().find(|e| e.content.member == f.id)?;
+            Some(Fronter {
+                start_time: Some(entry.content.start_time),
+                ..f
+            })
+        })
         .collect();
 
     fronters
@@ -14,7 +14,6 @@ pub struct FrontEntryContent {
     pub member: String, // member ID or custom front ID
     pub uid: String,    // System ID
 
-    #[allow(dead_code)] // todo. remove this when implementation done.
     #[serde(rename = "startTime")]
     #[serde(deserialize_with = "parse_epoch_millis_to_datetime_utc")]
     pub start_time: chrono::DateTime<chrono::Utc>,
@@ -31,12 +30,31 @@ where
         .ok_or_else(|| serde::de::Error::custom("Datime<Utc> from timestamp failed"))
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Fronter {
     pub id: String,
     pub name: String,
     pub avatar_url: String,
     pub vrchat_status_name: Option<String>,
+
+    // when the current front of this fronter started. populated from the front entries.
+    pub start_time: Option<chrono::DateTime<chrono::Utc>>,
+
+    // marked as private in the fronting source. never shown publicly on the website.
+    pub private: bool,
+
+    // normalized hex color such as "#ff00aa"
+    pub color: Option<String>,
+}
+
+/// Normalizes a hex color such as `FF00aa` or `#f0a` to `#ff00aa` / `#f0a`.
+/// Returns `None` for anything else, such that it can be safely embedded in CSS.
+pub fn normalize_hex_color(color: &str) -> Option<String> {
+    let hex = color.trim().trim_start_matches('#');
+
+    let valid = matches!(hex.len(), 3 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit());
+
+    valid.then(|| format!("#{}", hex.to_ascii_lowercase()))
 }
 
 impl Fronter {
@@ -47,6 +65,36 @@ impl Fronter {
     }
 }
 
+#[cfg(test)]
+impl Fronter {
+    /// A public fronter without avatar, color and start time. The ID is the lowercase name.
+    pub fn for_tests(name: &str) -> Self {
+        Self {
+            id: name.to_lowercase(),
+            name: name.to_owned(),
+            avatar_url: String::new(),
+            vrchat_status_name: None,
+            start_time: None,
+            private: false,
+            color: None,
+        }
+    }
+
+    pub fn with_id(self, id: &str) -> Self {
+        Self {
+            id: id.to_owned(),
+            ..self
+        }
+    }
+
+    pub fn with_start_time(self, start_time: chrono::DateTime<chrono::Utc>) -> Self {
+        Self {
+            start_time: Some(start_time),
+            ..self
+        }
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct CustomFront {
     pub content: CustomFrontContent,
@@ -60,6 +108,12 @@ pub struct CustomFrontContent {
     #[serde(rename = "avatarUrl")]
     #[serde(default)]
     pub avatar_url: String,
+
+    #[serde(default)]
+    pub private: bool,
+
+    #[serde(default)]
+    pub color: Option<String>,
 }
 
 impl From<&CustomFront> for Fronter {
@@ -69,6 +123,9 @@ impl From<&CustomFront> for Fronter {
             name: cf.content.name.clone(),
             avatar_url: cf.content.avatar_url.clone(),
             vrchat_status_name: None,
+            start_time: None,
+            private: cf.content.private,
+            color: cf.content.color.as_deref().and_then(normalize_hex_color),
         }
     }
 }
@@ -87,6 +144,12 @@ pub struct MemberContent {
     #[serde(default)]
     pub avatar_url: String,
 
+    #[serde(default)]
+    pub private: bool,
+
+    #[serde(default)]
+    pub color: Option<String>,
+
     #[serde(default)]
     pub info: serde_json::Value,
     // if the user uses the custom field "VRChat Status Name" on this member, then this will be
@@ -112,6 +175,9 @@ impl From<&Member> for Fronter {
             name: m.content.name.clone(),
             avatar_url: m.content.avatar_url.clone(),
             vrchat_status_name,
+            start_time: None,
+            private: m.content.private,
+            color: m.content.color.as_deref().and_then(normalize_hex_color),
         }
     }
 }
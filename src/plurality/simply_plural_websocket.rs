@@ -0,0 +1,191 @@
+use anyhow::{anyhow, Result};
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::{
+    net::TcpStream,
+    time::{sleep_until, Instant},
+};
+use tokio_tungstenite::{tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+use crate::users;
+
+const PING_INTERVAL: Duration = Duration::from_secs(10);
+const RECONNECT_BACKOFF_INITIAL: Duration = Duration::from_secs(1);
+const RECONNECT_BACKOFF_MAX: Duration = Duration::from_secs(300);
+
+// changes on any of these collections can change the rendered fronting status
+const FRONT_RELEVANT_TARGETS: [&str; 3] = ["frontHistory", "members", "customFronts"];
+
+type SocketConnection = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Notifies the updater as soon as the fronting source reports a change of the fronts.
+pub trait FrontChangeListener {
+    /// Waits until a front change is pushed. Connection failures are handled internally.
+    async fn wait_for_front_change(&mut self);
+}
+
+#[derive(Serialize)]
+struct AuthenticateRequest {
+    op: String,
+    token: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct SocketMessage {
+    msg: String,
+
+    #[serde(default)]
+    target: Option<String>,
+}
+
+pub struct SimplyPluralWebsocket {
+    url: String,
+    token: String,
+    connection: Option<SocketConnection>,
+    failed_connection_attempts: u32,
+    // kept across calls, since waiting may be interrupted by the polling heartbeat
+    reconnect_not_before: Option<Instant>,
+}
+
+impl SimplyPluralWebsocket {
+    pub fn new(config: &users::UserConfigForUpdater) -> Self {
+        Self {
+            url: config.simply_plural_websocket_url.clone(),
+            token: config.simply_plural_token.secret.clone(),
+            connection: None,
+            failed_connection_attempts: 0,
+            reconnect_not_before: None,
+        }
+    }
+
+    async fn next_front_change(&mut self) -> Result<()> {
+        if let Some(reconnect_not_before) = self.reconnect_not_before {
+            sleep_until(reconnect_not_before).await;
+            self.reconnect_not_before = None;
+        }
+
+        if self.connection.is_none() {
+            let connection = connect_and_authenticate(&self.url, &self.token).await?;
+            self.failed_connection_attempts = 0;
+            self.connection = Some(connection);
+        }
+
+        let connection = self
+            .connection
+            .as_mut()
+            .ok_or_else(|| anyhow!("Websocket not connected"))?;
+
+        let mut ping_interval = tokio::time::interval(PING_INTERVAL);
+
+        loop {
+            tokio::select! {
+                message = connection.next() => {
+                    let message = message.ok_or_else(|| anyhow!("Websocket closed"))??;
+                    if is_front_change(&message)? {
+                        return Ok(());
+                    }
+                }
+                _ = ping_interval.tick() => {
+                    connection.send(Message::Text("ping".to_owned())).await?;
+                }
+            }
+        }
+    }
+
+    fn reconnect_backoff(&self) -> Duration {
+        let exponent = self.failed_connection_attempts.min(16);
+        RECONNECT_BACKOFF_INITIAL
+            .saturating_mul(2_u32.pow(exponent))
+            .min(RECONNECT_BACKOFF_MAX)
+    }
+}
+
+impl FrontChangeListener for SimplyPluralWebsocket {
+    async fn wait_for_front_change(&mut self) {
+        loop {
+            match self.next_front_change().await {
+                Ok(()) => {
+                    eprintln!("SimplyPlural websocket reported a front change.");
+                    return;
+                }
+                Err(err) => {
+                    self.connection = None;
+                    let backoff = self.reconnect_backoff();
+                    self.failed_connection_attempts += 1;
+                    eprintln!(
+                        "Error in SimplyPlural websocket. Reconnecting in {}s. Error: {err}",
+                        backoff.as_secs()
+                    );
+                    self.reconnect_not_before = Some(Instant::now() + backoff);
+                }
+            }
+        }
+    }
+}
+
+async fn connect_and_authenticate(url: &str, token: &str) -> Result<SocketConnection> {
+    eprintln!("Connecting to SimplyPlural websocket...");
+    let (mut connection, _) = tokio_tungstenite::connect_async(url).await?;
+
+    let authenticate = AuthenticateRequest {
+        op: String::from("authenticate"),
+        token: token.to_owned(),
+    };
+    connection
+        .send(Message::Text(serde_json::to_string(&authenticate)?))
+        .await?;
+
+    while let Some(message) = connection.next().await {
+        if let Message::Text(text) = message? {
+            let response: SocketMessage = serde_json::from_str(&text)?;
+            if response.msg == "Successfully authenticated" {
+                eprintln!("SimplyPlural websocket authenticated.");
+                return Ok(connection);
+            }
+            return Err(anyhow!("Websocket authentication failed: {}", response.msg));
+        }
+    }
+
+    Err(anyhow!("Websocket closed during authentication"))
+}
+
+fn is_front_change(message: &Message) -> Result<bool> {
+    match message {
+        Message::Text(text) if text == "pong" => Ok(false),
+        Message::Text(text) => {
+            // unknown messages must not tear down the connection
+            let Ok(message) = serde_json::from_str::<SocketMessage>(text) else {
+                eprintln!("Ignoring unknown SimplyPlural websocket message: {text}");
+                return Ok(false);
+            };
+            let is_front_change = message.msg == "update"
+                && message
+                    .target
+                    .is_some_and(|t| FRONT_RELEVANT_TARGETS.contains(&t.as_str()));
+            Ok(is_front_change)
+        }
+        Message::Close(_) => Err(anyhow!("Websocket closed by server")),
+        _ => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_messages_are_ignored() -> Result<()> {
+        let front_change = r#"{"msg":"update","target":"frontHistory"}"#;
+
+        assert!(is_front_change(&Message::Text(front_change.to_owned()))?);
+        assert!(!is_front_change(&Message::Text(String::from("pong")))?);
+        assert!(!is_front_change(&Message::Text(String::from("not json")))?);
+        assert!(!is_front_change(&Message::Text(String::from(
+            r#"{"op":"ping"}"#
+        )))?);
+        assert!(is_front_change(&Message::Close(None)).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+use crate::database::Decrypted;
+use crate::plurality::{FrontChangeListener, SimplyPluralWebsocket};
+use crate::users::{self, UserConfigDbEntries};
+use anyhow::{anyhow, Result};
+use futures_util::{SinkExt, StreamExt};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::time::timeout;
+use tokio_tungstenite::{tungstenite::Message, WebSocketStream};
+
+const SIMPLY_PLURAL_TOKEN: &str = "sp_token_123";
+const TEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn websocket_config_for_tests(websocket_url: String) -> Result<users::UserConfigForUpdater> {
+    let db_config = UserConfigDbEntries::<Decrypted> {
+        system_name: Some("SP System".to_owned()),
+        simply_plural_token: Some(SIMPLY_PLURAL_TOKEN.into()),
+        ..Default::default()
+    };
+
+    let mut config = users::config_for_tests(&db_config)?;
+
+    config.simply_plural_websocket_url = websocket_url;
+
+    Ok(config)
+}
+
+// Local stand-in for the SimplyPlural socket. Accepts a connection and checks the authentication.
+async fn accept_authenticated(
+    listener: &TcpListener,
+) -> Result<WebSocketStream<tokio::net::TcpStream>> {
+    let (stream, _) = listener.accept().await?;
+    let mut socket = tokio_tungstenite::accept_async(stream).await?;
+
+    let auth = socket
+        .next()
+        .await
+        .ok_or_else(|| anyhow!("no authentication"))??;
+    let auth: serde_json::Value = serde_json::from_str(auth.to_text()?)?;
+    assert_eq!(auth["op"], "authenticate");
+    assert_eq!(auth["token"], SIMPLY_PLURAL_TOKEN);
+
+    socket
+        .send(Message::Text(
+            r#"{"msg":"Successfully authenticated"}"#.to_owned(),
+        ))
+        .await?;
+
+    Ok(socket)
+}
+
+#[tokio::test]
+async fn test_websocket_front_change_is_reported() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let config = websocket_config_for_tests(format!("ws://{}", listener.local_addr()?))?;
+
+    let server = tokio::spawn(async move {
+        let mut socket = accept_authenticated(&listener).await?;
+        socket.send(Message::Text("pong".to_owned())).await?;
+        socket
+            .send(Message::Text(
+                r#"{"msg":"update","target":"chatMessages","results":[]}"#.to_owned(),
+            ))
+            .await?;
+        socket
+            .send(Message::Text(
+                r#"{"msg":"update","target":"frontHistory","results":[]}"#.to_owned(),
+            ))
+            .await?;
+        // keep the connection open until the client is done
+        let _ = socket.next().await;
+        anyhow::Ok(())
+    });
+
+    let mut websocket = SimplyPluralWebsocket::new(&config);
+    timeout(TEST_TIMEOUT, websocket.wait_for_front_change()).await?;
+
+    server.abort();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_websocket_reconnects_after_connection_loss() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let config = websocket_config_for_tests(format!("ws://{}", listener.local_addr()?))?;
+
+    let server = tokio::spawn(async move {
+        let mut first_socket = accept_authenticated(&listener).await?;
+        first_socket.close(None).await?;
+
+        let mut second_socket = accept_authenticated(&listener).await?;
+        second_socket
+            .send(Message::Text(
+                r#"{"msg":"update","target":"members","results":[]}"#.to_owned(),
+            ))
+            .await?;
+        let _ = second_socket.next().await;
+        anyhow::Ok(())
+    });
+
+    let mut websocket = SimplyPluralWebsocket::new(&config);
+    timeout(TEST_TIMEOUT, websocket.wait_for_front_change()).await?;
+
+    server.abort();
+    Ok(())
+}
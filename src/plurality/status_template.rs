@@ -0,0 +1,236 @@
+use anyhow::{anyhow, Result};
+
+use crate::plurality::{truncate_to_length, LengthMeasure};
+
+/// Built-in templates of decreasing lengths for the aesthetics and information tradeoff.
+/// They're used, when the user hasn't configured own templates.
+pub const DEFAULT_STATUS_TEMPLATES: [&str; 4] = [
+    r#"{prefix} {names|join:", "}"#,
+    r#"{prefix}{names|join:","}"#,
+    r#"{prefix}{names|truncate|join:","}"#,
+    "{prefix} {count}#",
+];
+
+/// A status template such as `{prefix} {names|join:", "} ({count})`.
+///
+/// Placeholders:
+/// * `{prefix}`: the configured status prefix
+/// * `{count}`: the number of fronters
+/// * `{names}`: the fronter names. Supports the filters
+///   * `truncate`: truncate names to the configured length
+///   * `truncate:<n>`: truncate names to `n` characters (graphemes)
+///   * `join:"<separator>"`: join names with the separator. Defaults to `", "`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StatusTemplate {
+    parts: Vec<TemplatePart>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum TemplatePart {
+    Text(String),
+    Prefix,
+    Count,
+    Names {
+        truncate: Option<NameTruncation>,
+        separator: String,
+        // only set, when shortening the status. not part of the template syntax.
+        max_names: Option<usize>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum NameTruncation {
+    Configured,
+    Graphemes(usize),
+}
+
+pub struct TemplateValues<'a> {
+    pub prefix: &'a str,
+    pub names: &'a [String],
+    pub count: usize,
+    pub truncate_names_to: usize,
+}
+
+impl StatusTemplate {
+    pub fn parse(template: &str) -> Result<Self> {
+        let mut parts = vec![];
+        let mut text = String::new();
+        let mut chars = template.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' => {
+                    if !text.is_empty() {
+                        parts.push(TemplatePart::Text(std::mem::take(&mut text)));
+                    }
+                    let placeholder = take_placeholder(&mut chars)
+                        .ok_or_else(|| anyhow!("Unclosed '{{' in template '{template}'"))?;
+                    parts.push(parse_placeholder(&placeholder).map_err(|e| {
+                        anyhow!(
+                            "Invalid placeholder '{{{placeholder}}}' in template '{template}': {e}"
+                        )
+                    })?);
+                }
+                '}' => return Err(anyhow!("Unopened '}}' in template '{template}'")),
+                _ => text.push(c),
+            }
+        }
+
+        if !text.is_empty() {
+            parts.push(TemplatePart::Text(text));
+        }
+
+        Ok(Self { parts })
+    }
+
+    pub fn render(&self, values: &TemplateValues) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                TemplatePart::Text(text) => text.clone(),
+                TemplatePart::Prefix => values.prefix.to_owned(),
+                TemplatePart::Count => values.count.to_string(),
+                TemplatePart::Names {
+                    truncate,
+                    separator,
+                    max_names,
+                } => {
+                    let truncate_to = truncate.as_ref().map(|t| match t {
+                        NameTruncation::Configured => values.truncate_names_to,
+                        NameTruncation::Graphemes(n) => *n,
+                    });
+                    values
+                        .names
+                        .iter()
+                        .take(max_names.unwrap_or(values.names.len()))
+                        .map(|name| {
+                            truncate_to.map_or_else(
+                                || name.clone(),
+                                |n| truncate_to_length(name, n, LengthMeasure::Graphemes),
+                            )
+                        })
+                        .collect::<Vec<String>>()
+                        .join(separator)
+                }
+            })
+            .collect()
+    }
+
+    /// The same template with the names truncated to the configured length and only the first `max_names` of them.
+    /// Shortens the status, while keeping the text of the template.
+    pub fn with_shortened_names(&self, max_names: usize) -> Self {
+        let parts = self
+            .parts
+            .iter()
+            .map(|part| match part {
+                TemplatePart::Names { separator, .. } => TemplatePart::Names {
+                    truncate: Some(NameTruncation::Configured),
+                    separator: separator.clone(),
+                    max_names: Some(max_names),
+                },
+                other => other.clone(),
+            })
+            .collect();
+
+        Self { parts }
+    }
+}
+
+pub fn parse_status_templates<S: AsRef<str>>(templates: &[S]) -> Result<Vec<StatusTemplate>> {
+    if templates.is_empty() {
+        return Err(anyhow!("At least one status template is required"));
+    }
+
+    templates
+        .iter()
+        .map(|t| StatusTemplate::parse(t.as_ref()))
+        .collect()
+}
+
+pub fn default_status_templates_strings() -> Vec<String> {
+    DEFAULT_STATUS_TEMPLATES.map(String::from).to_vec()
+}
+
+// Reads everything up to the closing '}'. Braces within quotes are part of the placeholder.
+fn take_placeholder(chars: &mut std::str::Chars) -> Option<String> {
+    let mut placeholder = String::new();
+    let mut in_quotes = false;
+
+    for c in chars.by_ref() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '}' if !in_quotes => return Some(placeholder),
+            _ => {}
+        }
+        placeholder.push(c);
+    }
+
+    None
+}
+
+fn parse_placeholder(placeholder: &str) -> Result<TemplatePart> {
+    let mut segments = split_outside_quotes(placeholder, '|').into_iter();
+    let name = segments.next().unwrap_or_default();
+
+    match name.trim() {
+        "prefix" | "count" if segments.len() > 0 => Err(anyhow!("'{name}' has no filters")),
+        "prefix" => Ok(TemplatePart::Prefix),
+        "count" => Ok(TemplatePart::Count),
+        "names" => {
+            let mut truncate = None;
+            let mut separator = String::from(", ");
+
+            for filter in segments {
+                let (filter_name, argument) = filter
+                    .split_once(':')
+                    .map_or((filter.as_str(), None), |(f, a)| (f, Some(a.trim())));
+
+                match (filter_name.trim(), argument) {
+                    ("truncate", None) => truncate = Some(NameTruncation::Configured),
+                    ("truncate", Some(n)) => {
+                        truncate =
+                            Some(NameTruncation::Graphemes(n.parse().map_err(|_| {
+                                anyhow!("'truncate' expects a number but got '{n}'")
+                            })?));
+                    }
+                    ("join", Some(sep)) => separator = parse_quoted(sep)?,
+                    (other, _) => return Err(anyhow!("Unknown filter '{other}'")),
+                }
+            }
+
+            Ok(TemplatePart::Names {
+                truncate,
+                separator,
+                max_names: None,
+            })
+        }
+        other => Err(anyhow!("Unknown placeholder '{other}'")),
+    }
+}
+
+fn split_outside_quotes(s: &str, delimiter: char) -> Vec<String> {
+    let mut segments = vec![];
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in s.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                current.push(c);
+            }
+            c if c == delimiter && !in_quotes => segments.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    segments.push(current);
+
+    segments
+}
+
+fn parse_quoted(s: &str) -> Result<String> {
+    s.strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .map(ToString::to_string)
+        .ok_or_else(|| anyhow!("Expected a quoted string but got '{s}'"))
+}
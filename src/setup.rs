@@ -1,4 +1,5 @@
 use crate::database;
+use crate::platforms;
 use crate::updater;
 use crate::users;
 use anyhow::Result;
@@ -13,10 +14,9 @@ pub async fn application_setup(cli_args: &CliArgs) -> Result<ApplicationSetup> {
         .connect(&cli_args.database_url)
         .await?;
 
-    let client: reqwest::Client = reqwest::Client::builder()
-        .cookie_store(true)
-        .timeout(Duration::from_secs(cli_args.request_timeout))
-        .build()?;
+    let client = create_http_client(cli_args)?;
+    let avatar_client =
+        platforms::AvatarHttpClient::new(Duration::from_secs(cli_args.request_timeout))?;
 
     let jwt_secret = users::ApplicationJwtSecret {
         inner: cli_args.jwt_application_secret.clone(),
@@ -26,41 +26,68 @@ pub async fn application_setup(cli_args: &CliArgs) -> Result<ApplicationSetup> {
         inner: cli_args.application_user_secrets.clone(),
     };
 
-    let shared_updaters = updater::UpdaterManager::new(cli_args);
+    let shared_updaters = updater::UpdaterManager::new(cli_args, Some(db_pool.clone()));
 
     Ok(ApplicationSetup {
         db_pool,
         client,
+        avatar_client,
         jwt_secret,
         application_user_secrets,
         shared_updaters,
+        vrchat_pending_auths: platforms::VRChatPendingAuths::default(),
     })
 }
 
+pub fn create_http_client(cli_args: &CliArgs) -> Result<reqwest::Client> {
+    let client = reqwest::Client::builder()
+        .cookie_store(true)
+        .timeout(Duration::from_secs(cli_args.request_timeout))
+        .build()?;
+
+    Ok(client)
+}
+
 #[derive(Parser, Debug, Clone, Default)]
 #[clap(author, version, about, long_about = None)]
 pub struct CliArgs {
-    #[arg(long, env)]
+    /// Run a single updater from the local config file without database and webserver.
+    #[arg(long, default_value_t = false, action = clap::ArgAction::SetTrue)]
+    pub local: bool,
+
+    #[arg(long, env, required_unless_present = "local", default_value_t)]
     pub database_url: String,
 
     #[arg(short, long, env, default_value_t = 5)]
     pub request_timeout: u64,
 
-    #[arg(short, long, env)]
+    #[arg(short, long, env, required_unless_present = "local", default_value_t)]
     pub jwt_application_secret: String,
 
-    #[arg(short, long, env)]
+    #[arg(short, long, env, required_unless_present = "local", default_value_t)]
     pub application_user_secrets: String,
 
     #[arg(short, long, env, default_value_t = false, action = clap::ArgAction::SetTrue)]
     pub discord_status_message_updater_available: bool,
+
+    /// Seconds for which the fronts fetched by the updaters are served on the website,
+    /// before the website fetches them again by itself.
+    #[arg(long, env, default_value_t = 120)]
+    pub website_fronts_cache_ttl: u64,
+
+    /// Token of the discord bot, which answers slash commands like `/fronting @user`.
+    /// The bot is only started, if this is set.
+    #[arg(long, env)]
+    pub discord_bot_token: Option<String>,
 }
 
 #[derive(Clone)]
 pub struct ApplicationSetup {
     pub db_pool: sqlx::PgPool,
     pub client: reqwest::Client,
+    pub avatar_client: platforms::AvatarHttpClient,
     pub jwt_secret: users::ApplicationJwtSecret,
     pub application_user_secrets: database::ApplicationUserSecrets,
     pub shared_updaters: updater::UpdaterManager,
+    pub vrchat_pending_auths: platforms::VRChatPendingAuths,
 }
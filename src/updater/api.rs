@@ -1,4 +1,5 @@
 use crate::database;
+use crate::http;
 use crate::http::HttpResult;
 use crate::setup;
 use crate::updater::{manager, work_loop};
@@ -6,6 +7,7 @@ use crate::users;
 use crate::users::UserId;
 use anyhow::Result;
 use rocket::{serde::json::Json, State};
+use serde::Serialize;
 use sqlx::PgPool;
 
 #[get("/api/updaters/status")]
@@ -21,6 +23,35 @@ pub fn get_api_updaters_status(
     Ok(Json(updaters_state))
 }
 
+#[get("/api/updaters/history?<page>&<page_size>")]
+pub async fn get_api_updaters_history(
+    jwt: HttpResult<users::Jwt>,
+    db_pool: &State<PgPool>,
+    page: Option<u32>,
+    page_size: Option<u32>,
+) -> HttpResult<Json<UpdaterHistoryPage>> {
+    let user_id = jwt?.user_id()?;
+
+    let pagination = http::Pagination::new(page, page_size);
+
+    let events =
+        database::get_updater_events(db_pool, &user_id, pagination.limit(), pagination.offset())
+            .await?;
+
+    Ok(Json(UpdaterHistoryPage {
+        page: pagination.page,
+        page_size: pagination.page_size,
+        events,
+    }))
+}
+
+#[derive(Serialize)]
+pub struct UpdaterHistoryPage {
+    pub page: u32,
+    pub page_size: u32,
+    pub events: Vec<database::UpdaterEvent>,
+}
+
 #[post("/api/updaters/restart")]
 pub async fn post_api_updaters_restart(
     jwt: HttpResult<users::Jwt>,
@@ -68,7 +99,7 @@ pub async fn restart_all_user_updaters_for_app_startups(
     Ok(())
 }
 
-async fn restart_updater_for_user(
+pub async fn restart_updater_for_user(
     user_id: &UserId,
     db_pool: &PgPool,
     application_user_secrets: &database::ApplicationUserSecrets,
@@ -81,6 +112,7 @@ async fn restart_updater_for_user(
 
     let (config, _) = users::create_config_with_strong_constraints(user_id, client, &db_config)?;
 
+    let () = shared_updaters.invalidate_website_config(user_id)?;
     let () = shared_updaters.restart_updater(user_id, config)?;
 
     eprintln!("Restarting user updaters {user_id}. DONE.");
@@ -0,0 +1,278 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::updater::{Platform, RateLimited, UpdaterErrorKind};
+
+/// Failing platforms are retried at least this often.
+pub const MAX_BACKOFF: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PlatformBackoff {
+    RetryAt {
+        consecutive_failures: u32,
+        at: Instant,
+    },
+    // e.g. invalid credentials. The updaters are restarted with the new config, once the user changes it.
+    PausedUntilConfigChange,
+}
+
+/// When failing platforms are tried again. Healthy platforms keep their normal interval.
+#[derive(Default)]
+pub struct Backoffs {
+    platforms: HashMap<Platform, PlatformBackoff>,
+    // without fronts, none of the platforms can be updated
+    fronts_fetch: Option<PlatformBackoff>,
+}
+
+impl Backoffs {
+    /// The time of the next update, given the time it would normally happen.
+    /// None, if the platform is paused.
+    pub fn next_attempt(&self, platform: &Platform, normally_at: Instant) -> Option<Instant> {
+        match self.platforms.get(platform) {
+            None => Some(normally_at),
+            Some(PlatformBackoff::RetryAt { at, .. }) => Some(normally_at.max(*at)),
+            Some(PlatformBackoff::PausedUntilConfigChange) => None,
+        }
+    }
+
+    pub fn is_backing_off(&self, platform: &Platform) -> bool {
+        self.platforms.contains_key(platform)
+    }
+
+    /// The interval is the normal time between two updates.
+    pub fn record(
+        &mut self,
+        platform: &Platform,
+        result: &Result<()>,
+        interval: Duration,
+        now: Instant,
+    ) {
+        let Err(error) = result else {
+            self.platforms.remove(platform);
+            return;
+        };
+
+        let (consecutive_failures, backoff) = next_backoff(
+            self.platforms.get(platform),
+            UpdaterErrorKind::of(error).retryable(),
+            error,
+            interval,
+            now,
+        );
+
+        eprintln!(
+            "{platform} failed {consecutive_failures} time(s) in a row. Backoff: {backoff:?}"
+        );
+
+        self.platforms.insert(platform.to_owned(), backoff);
+    }
+
+    /// Whether the fronts may be fetched now. False, while the fronting source is backing off or paused.
+    pub fn is_fronts_fetch_due(&self, now: Instant) -> bool {
+        match &self.fronts_fetch {
+            None => true,
+            Some(PlatformBackoff::RetryAt { at, .. }) => *at <= now,
+            Some(PlatformBackoff::PausedUntilConfigChange) => false,
+        }
+    }
+
+    /// Like `record`, but for fetching the fronts. The interval is the time between two fetches.
+    pub fn record_fronts_fetch<T>(&mut self, result: &Result<T>, interval: Duration, now: Instant) {
+        let Err(error) = result else {
+            self.fronts_fetch = None;
+            return;
+        };
+
+        let (consecutive_failures, backoff) = next_backoff(
+            self.fronts_fetch.as_ref(),
+            UpdaterErrorKind::of_fronts_fetch(error).retryable(),
+            error,
+            interval,
+            now,
+        );
+
+        eprintln!(
+            "Fetching fronts failed {consecutive_failures} time(s) in a row. Backoff: {backoff:?}"
+        );
+
+        self.fronts_fetch = Some(backoff);
+    }
+}
+
+// returns the number of consecutive failures including this one
+fn next_backoff(
+    previous: Option<&PlatformBackoff>,
+    retryable: bool,
+    error: &anyhow::Error,
+    interval: Duration,
+    now: Instant,
+) -> (u32, PlatformBackoff) {
+    let consecutive_failures = match previous {
+        Some(PlatformBackoff::RetryAt {
+            consecutive_failures,
+            ..
+        }) => consecutive_failures.saturating_add(1),
+        _ => 1,
+    };
+
+    let backoff = if retryable {
+        let retry_after = error
+            .downcast_ref::<RateLimited>()
+            .and_then(|r| r.retry_after);
+        let delay =
+            retry_after.unwrap_or_else(|| exponential_delay(interval, consecutive_failures));
+
+        PlatformBackoff::RetryAt {
+            consecutive_failures,
+            at: now + delay,
+        }
+    } else {
+        PlatformBackoff::PausedUntilConfigChange
+    };
+
+    (consecutive_failures, backoff)
+}
+
+// the first retry happens after the normal interval and the delay doubles with each further failure
+fn exponential_delay(interval: Duration, consecutive_failures: u32) -> Duration {
+    let factor = 2_u32.saturating_pow(consecutive_failures.saturating_sub(1));
+    interval.saturating_mul(factor).min(MAX_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    const INTERVAL: Duration = Duration::from_secs(60);
+
+    fn failed() -> Result<()> {
+        Err(anyhow!("timeout"))
+    }
+
+    fn is_due(backoffs: &Backoffs, platform: &Platform, now: Instant) -> bool {
+        backoffs
+            .next_attempt(platform, now)
+            .is_some_and(|at| at <= now)
+    }
+
+    #[test]
+    fn test_failing_platform_backs_off_exponentially_and_recovers() {
+        let mut backoffs = Backoffs::default();
+        let start = Instant::now();
+
+        backoffs.record(&Platform::VRChat, &failed(), INTERVAL, start);
+        assert!(!is_due(&backoffs, &Platform::VRChat, start + INTERVAL / 2));
+        assert!(is_due(&backoffs, &Platform::VRChat, start + INTERVAL));
+
+        let second = start + INTERVAL;
+        backoffs.record(&Platform::VRChat, &failed(), INTERVAL, second);
+        assert!(!is_due(&backoffs, &Platform::VRChat, second + INTERVAL));
+        assert!(is_due(&backoffs, &Platform::VRChat, second + 2 * INTERVAL));
+
+        // healthy platforms keep their normal cadence
+        assert!(is_due(&backoffs, &Platform::DiscordStatusMessage, second));
+
+        backoffs.record(&Platform::VRChat, &Ok(()), INTERVAL, second);
+        assert!(is_due(&backoffs, &Platform::VRChat, second));
+    }
+
+    #[test]
+    fn test_backoff_is_capped() {
+        assert_eq!(exponential_delay(INTERVAL, 1), INTERVAL);
+        assert_eq!(exponential_delay(INTERVAL, 3), 4 * INTERVAL);
+        assert_eq!(exponential_delay(INTERVAL, 500), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn test_expired_sessions_are_retried_but_rejected_logins_pause() {
+        let mut backoffs = Backoffs::default();
+        let now = Instant::now();
+
+        let expired = anyhow!("401 Unauthorized").context(crate::platforms::VRChatSessionExpired);
+        backoffs.record(&Platform::VRChat, &Err(expired), INTERVAL, now);
+        assert!(is_due(&backoffs, &Platform::VRChat, now + INTERVAL));
+
+        // the renewal itself failed, e.g. due to a changed password
+        let rejected = anyhow!(vrchatapi::apis::Error::<
+            vrchatapi::apis::users_api::UpdateUserError,
+        >::ResponseError(vrchatapi::apis::ResponseContent {
+            status: reqwest::StatusCode::UNAUTHORIZED,
+            content: String::new(),
+            entity: None,
+        }));
+        backoffs.record(&Platform::VRChat, &Err(rejected), INTERVAL, now);
+        assert!(!is_due(
+            &backoffs,
+            &Platform::VRChat,
+            now + 100 * MAX_BACKOFF
+        ));
+    }
+
+    #[test]
+    fn test_fronts_fetch_backs_off_and_pauses_on_an_invalid_token() {
+        let mut backoffs = Backoffs::default();
+        let now = Instant::now();
+        assert!(backoffs.is_fronts_fetch_due(now));
+
+        backoffs.record_fronts_fetch(&failed(), INTERVAL, now);
+        assert!(!backoffs.is_fronts_fetch_due(now + INTERVAL / 2));
+        assert!(backoffs.is_fronts_fetch_due(now + INTERVAL));
+
+        backoffs.record_fronts_fetch(&failed(), INTERVAL, now);
+        assert!(!backoffs.is_fronts_fetch_due(now + INTERVAL));
+        assert!(backoffs.is_fronts_fetch_due(now + 2 * INTERVAL));
+
+        backoffs.record_fronts_fetch(&Ok(()), INTERVAL, now);
+        assert!(backoffs.is_fronts_fetch_due(now));
+
+        let invalid_token = anyhow!(vrchatapi::apis::Error::<
+            vrchatapi::apis::users_api::GetUserError,
+        >::ResponseError(vrchatapi::apis::ResponseContent {
+            status: reqwest::StatusCode::UNAUTHORIZED,
+            content: String::new(),
+            entity: None,
+        }));
+        backoffs.record_fronts_fetch::<()>(&Err(invalid_token), INTERVAL, now);
+        assert!(!backoffs.is_fronts_fetch_due(now + 100 * MAX_BACKOFF));
+    }
+
+    #[test]
+    fn test_auth_errors_pause_and_rate_limits_honor_retry_after() {
+        let mut backoffs = Backoffs::default();
+        let now = Instant::now();
+
+        backoffs.record(
+            &Platform::VRChat,
+            &Err(anyhow!(crate::platforms::VRChatNeedsTwoFactorAuth)),
+            INTERVAL,
+            now,
+        );
+        assert!(!is_due(
+            &backoffs,
+            &Platform::VRChat,
+            now + 100 * MAX_BACKOFF
+        ));
+
+        let rate_limited = Err(anyhow!(RateLimited {
+            retry_after: Some(Duration::from_secs(300)),
+        }));
+        backoffs.record(
+            &Platform::DiscordStatusMessage,
+            &rate_limited,
+            INTERVAL,
+            now,
+        );
+        assert!(!is_due(
+            &backoffs,
+            &Platform::DiscordStatusMessage,
+            now + Duration::from_secs(299)
+        ));
+        assert!(is_due(
+            &backoffs,
+            &Platform::DiscordStatusMessage,
+            now + Duration::from_secs(300)
+        ));
+    }
+}
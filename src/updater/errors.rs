@@ -0,0 +1,262 @@
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::platforms;
+
+/// What went wrong in an updater, such that the website can show how to fix it.
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+pub enum UpdaterErrorKind {
+    // the fronts couldn't be fetched due to an invalid Simply Plural / PluralKit token
+    InvalidFrontingSourceToken,
+    FrontingSourceUnavailable,
+    InvalidCredentials,
+    // the VRChat login expired and only the user can login again with a 2FA code
+    NeedsTwoFactorAuth,
+    // the VRChat session expired and is renewed with the stored login
+    SessionExpired,
+    RateLimited,
+    Network,
+    PlatformUnavailable,
+    Unknown,
+}
+
+impl UpdaterErrorKind {
+    pub fn of(error: &anyhow::Error) -> Self {
+        if error.is::<platforms::VRChatNeedsTwoFactorAuth>() {
+            return Self::NeedsTwoFactorAuth;
+        }
+        if error.is::<platforms::VRChatSessionExpired>() {
+            return Self::SessionExpired;
+        }
+        if error.is::<RateLimited>() {
+            return Self::RateLimited;
+        }
+
+        let reqwest_error = error
+            .chain()
+            .find_map(|e| e.downcast_ref::<reqwest::Error>());
+
+        let status = platforms::vrchat_response_status(error)
+            .or_else(|| reqwest_error.and_then(reqwest::Error::status));
+
+        match status {
+            Some(StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) => Self::InvalidCredentials,
+            Some(StatusCode::TOO_MANY_REQUESTS) => Self::RateLimited,
+            Some(status) if status.is_server_error() => Self::PlatformUnavailable,
+            None if reqwest_error.is_some_and(|e| e.is_timeout() || e.is_connect()) => {
+                Self::Network
+            }
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Like `of`, but for errors while fetching the fronts from the fronting source.
+    pub fn of_fronts_fetch(error: &anyhow::Error) -> Self {
+        match Self::of(error) {
+            Self::InvalidCredentials => Self::InvalidFrontingSourceToken,
+            Self::Network | Self::PlatformUnavailable | Self::Unknown => {
+                Self::FrontingSourceUnavailable
+            }
+            kind => kind,
+        }
+    }
+
+    /// Whether the error may go away by itself, when trying again later.
+    pub const fn retryable(self) -> bool {
+        match self {
+            Self::InvalidFrontingSourceToken
+            | Self::InvalidCredentials
+            | Self::NeedsTwoFactorAuth => false,
+            Self::FrontingSourceUnavailable
+            | Self::SessionExpired
+            | Self::RateLimited
+            | Self::Network
+            | Self::PlatformUnavailable
+            | Self::Unknown => true,
+        }
+    }
+
+    pub const fn hint(self) -> &'static str {
+        match self {
+            Self::InvalidFrontingSourceToken => {
+                "Check the Simply Plural or PluralKit token in your settings."
+            }
+            Self::FrontingSourceUnavailable => {
+                "The fronts couldn't be fetched. This usually resolves itself."
+            }
+            Self::InvalidCredentials => "Check the login or token of this platform in your settings.",
+            Self::NeedsTwoFactorAuth => "Login to VRChat again with your 2FA code.",
+            Self::SessionExpired => "The login is renewed automatically with your stored login.",
+            Self::RateLimited => {
+                "The platform received too many updates. Updates continue once it allows them again."
+            }
+            Self::Network => "The platform couldn't be reached. This usually resolves itself.",
+            Self::PlatformUnavailable => {
+                "The platform has problems on its side. This usually resolves itself."
+            }
+            Self::Unknown => "If this persists, please report the error message.",
+        }
+    }
+}
+
+/// The platform asked to wait before sending further updates.
+#[derive(Debug)]
+pub struct RateLimited {
+    pub retry_after: Option<Duration>,
+}
+
+impl RateLimited {
+    /// Only the delay in seconds is understood from the `Retry-After` header, not the HTTP date.
+    pub fn from_response(response: &reqwest::Response) -> Option<Self> {
+        if response.status() != StatusCode::TOO_MANY_REQUESTS {
+            return None;
+        }
+
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<f64>().ok())
+            .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+            .map(Duration::from_secs_f64);
+
+        Some(Self { retry_after })
+    }
+}
+
+impl std::fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.retry_after {
+            Some(retry_after) => write!(
+                f,
+                "Rate limited by the platform. Retrying after {}s.",
+                retry_after.as_secs()
+            ),
+            None => write!(f, "Rate limited by the platform."),
+        }
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UpdaterError {
+    pub kind: UpdaterErrorKind,
+    pub message: String,
+    pub retryable: bool,
+    pub hint: &'static str,
+    // start of the current streak of failures
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub consecutive_failures: u32,
+}
+
+impl UpdaterError {
+    /// Continues the streak of failures of the previous error, if there is one.
+    pub fn record(previous: Option<Self>, error: &anyhow::Error) -> Self {
+        Self::record_at(previous, UpdaterErrorKind::of(error), error, Utc::now())
+    }
+
+    pub fn record_fronts_fetch(previous: Option<Self>, error: &anyhow::Error) -> Self {
+        Self::record_at(
+            previous,
+            UpdaterErrorKind::of_fronts_fetch(error),
+            error,
+            Utc::now(),
+        )
+    }
+
+    fn record_at(
+        previous: Option<Self>,
+        kind: UpdaterErrorKind,
+        error: &anyhow::Error,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let (first_seen, previous_failures) =
+            previous.map_or((now, 0), |p| (p.first_seen, p.consecutive_failures));
+
+        Self {
+            kind,
+            message: error.to_string(),
+            retryable: kind.retryable(),
+            hint: kind.hint(),
+            first_seen,
+            last_seen: now,
+            consecutive_failures: previous_failures.saturating_add(1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use chrono::Duration;
+    use vrchatapi::apis::{users_api, Error as VrcError, ResponseContent};
+
+    fn vrchat_response_error(status: StatusCode) -> anyhow::Error {
+        anyhow!(VrcError::<users_api::UpdateUserError>::ResponseError(
+            ResponseContent {
+                status,
+                content: String::new(),
+                entity: None,
+            }
+        ))
+    }
+
+    #[test]
+    fn test_errors_are_classified() {
+        assert_eq!(
+            UpdaterErrorKind::of(&vrchat_response_error(StatusCode::TOO_MANY_REQUESTS)),
+            UpdaterErrorKind::RateLimited
+        );
+        assert_eq!(
+            UpdaterErrorKind::of(&vrchat_response_error(StatusCode::UNAUTHORIZED)),
+            UpdaterErrorKind::InvalidCredentials
+        );
+        assert_eq!(
+            UpdaterErrorKind::of_fronts_fetch(&vrchat_response_error(StatusCode::UNAUTHORIZED)),
+            UpdaterErrorKind::InvalidFrontingSourceToken
+        );
+        assert_eq!(
+            UpdaterErrorKind::of(&anyhow!(platforms::VRChatNeedsTwoFactorAuth)),
+            UpdaterErrorKind::NeedsTwoFactorAuth
+        );
+        assert_eq!(
+            UpdaterErrorKind::of(&anyhow!("something else")),
+            UpdaterErrorKind::Unknown
+        );
+        assert_eq!(
+            UpdaterErrorKind::of(
+                &vrchat_response_error(StatusCode::UNAUTHORIZED)
+                    .context(platforms::VRChatSessionExpired)
+            ),
+            UpdaterErrorKind::SessionExpired
+        );
+        assert!(!UpdaterErrorKind::NeedsTwoFactorAuth.retryable());
+        assert!(UpdaterErrorKind::RateLimited.retryable());
+    }
+
+    #[test]
+    fn test_consecutive_failures_continue_the_streak() {
+        let start = Utc::now();
+        let later = start + Duration::seconds(60);
+
+        let first =
+            UpdaterError::record_at(None, UpdaterErrorKind::Network, &anyhow!("timeout"), start);
+        let second = UpdaterError::record_at(
+            Some(first),
+            UpdaterErrorKind::RateLimited,
+            &anyhow!("429"),
+            later,
+        );
+
+        assert_eq!(second.kind, UpdaterErrorKind::RateLimited);
+        assert_eq!(second.message, "429");
+        assert_eq!(second.first_seen, start);
+        assert_eq!(second.last_seen, later);
+        assert_eq!(second.consecutive_failures, 2);
+    }
+}
@@ -1,27 +1,88 @@
+use crate::database;
+use crate::plurality;
 use crate::setup;
 use crate::updater::work_loop;
 use crate::users;
 use crate::users::UserId;
 use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
 
 type SharedMutable<T> = Arc<Mutex<T>>;
 type ThreadSafePerUser<T> = SharedMutable<HashMap<UserId, T>>;
 
+const UPDATER_EVENTS_RETENTION_DAYS: i32 = 30;
+const UPDATER_EVENTS_PRUNE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+// viewers lagging behind more than this skip to the most recent fronts
+const FRONT_CHANGES_CHANNEL_CAPACITY: usize = 16;
+
+/// Fronts as last fetched by the updater of a user. Shared with the website.
+#[derive(Clone, Debug)]
+pub struct CachedFronts {
+    pub fronts: Vec<plurality::Fronter>,
+    pub fetched_at: DateTime<Utc>,
+}
+
+impl CachedFronts {
+    pub fn now(fronts: Vec<plurality::Fronter>) -> Self {
+        Self {
+            fronts,
+            fetched_at: Utc::now(),
+        }
+    }
+
+    pub fn age(&self) -> Duration {
+        (Utc::now() - self.fetched_at).to_std().unwrap_or_default()
+    }
+}
+
 #[derive(Clone)]
 pub struct UpdaterManager {
     pub tasks: ThreadSafePerUser<work_loop::CancleableUpdater>,
     pub statuses: ThreadSafePerUser<work_loop::UserUpdatersStatuses>,
+    pub fronts: ThreadSafePerUser<CachedFronts>,
+    pub front_changes: ThreadSafePerUser<broadcast::Sender<CachedFronts>>,
+    // concurrent viewers wait for the same fetch instead of fetching the fronts themselves
+    pub fronts_fetches: ThreadSafePerUser<Arc<tokio::sync::Mutex<()>>>,
+    // the website reads the config on every request. cleared, when the user changes it.
+    pub website_configs: ThreadSafePerUser<Arc<users::UserConfigForUpdater>>,
+    // old updater events are deleted after the first update since startup and then once a day
+    pub updater_events_pruned_at: ThreadSafePerUser<DateTime<Utc>>,
+    pub fronts_cache_ttl: Duration,
     pub discord_status_message_available: bool,
+    // the discord bot of this deployment. also used by the nickname updaters.
+    pub discord_bot_token: Option<String>,
+    // VRChat only accepts OSC messages from the same machine or network
+    pub vrchat_osc_available: bool,
+    // None when running without database, e.g. in local mode
+    pub db_pool: Option<PgPool>,
+    // for updaters, which renew and store user secrets themselves
+    pub application_user_secrets: database::ApplicationUserSecrets,
 }
 
 impl UpdaterManager {
-    pub fn new(cli_args: &setup::CliArgs) -> Self {
+    pub fn new(cli_args: &setup::CliArgs, db_pool: Option<PgPool>) -> Self {
         Self {
             tasks: Arc::new(Mutex::new(HashMap::new())),
             statuses: Arc::new(Mutex::new(HashMap::new())),
+            fronts: Arc::new(Mutex::new(HashMap::new())),
+            front_changes: Arc::new(Mutex::new(HashMap::new())),
+            fronts_fetches: Arc::new(Mutex::new(HashMap::new())),
+            website_configs: Arc::new(Mutex::new(HashMap::new())),
+            updater_events_pruned_at: Arc::new(Mutex::new(HashMap::new())),
+            fronts_cache_ttl: Duration::from_secs(cli_args.website_fronts_cache_ttl),
             discord_status_message_available: cli_args.discord_status_message_updater_available,
+            discord_bot_token: cli_args.discord_bot_token.clone(),
+            vrchat_osc_available: cli_args.local,
+            db_pool,
+            application_user_secrets: database::ApplicationUserSecrets {
+                inner: cli_args.application_user_secrets.clone(),
+            },
         }
     }
 
@@ -48,6 +109,195 @@ impl UpdaterManager {
         Ok(())
     }
 
+    /// The last fetched fronts of the user, if they're not older than the cache TTL.
+    pub fn get_fresh_cached_fronts(&self, user_id: &UserId) -> Result<Option<CachedFronts>> {
+        Ok(self
+            .fronts
+            .lock()
+            .map_err(|e| anyhow!(e.to_string()))?
+            .get(user_id)
+            .filter(|cached| cached.age() <= self.fronts_cache_ttl)
+            .cloned())
+    }
+
+    /// The fresh cached fronts of the user. Fetches and caches them, if there are none.
+    /// Only one fetch per user runs at a time.
+    pub async fn get_or_fetch_fronts(
+        &self,
+        user_id: &UserId,
+        config: &users::UserConfigForUpdater,
+    ) -> Result<CachedFronts> {
+        if let Some(cached_fronts) = self.get_fresh_cached_fronts(user_id)? {
+            return Ok(cached_fronts);
+        }
+
+        let fetch_lock = self
+            .fronts_fetches
+            .lock()
+            .map_err(|e| anyhow!(e.to_string()))?
+            .entry(user_id.to_owned())
+            .or_default()
+            .clone();
+        let _fetching = fetch_lock.lock().await;
+
+        // fetched by someone else while waiting
+        if let Some(cached_fronts) = self.get_fresh_cached_fronts(user_id)? {
+            return Ok(cached_fronts);
+        }
+
+        eprintln!("{user_id}: No fresh cached fronts. Fetching fronts");
+
+        let fronts = plurality::fetch_fronts(config).await?;
+
+        let cached_fronts = CachedFronts::now(fronts);
+        self.set_cached_fronts(user_id, cached_fronts.clone())?;
+        Ok(cached_fronts)
+    }
+
+    /// Caches the fronts and notifies the subscribers of the user, if the fronts changed.
+    pub fn set_cached_fronts(&self, user_id: &UserId, cached_fronts: CachedFronts) -> Result<()> {
+        let previous = self
+            .fronts
+            .lock()
+            .map_err(|e| anyhow!(e.to_string()))?
+            .insert(user_id.to_owned(), cached_fronts.clone());
+
+        if previous.is_some_and(|p| p.fronts == cached_fronts.fronts) {
+            return Ok(());
+        }
+
+        if let Some(sender) = self
+            .front_changes
+            .lock()
+            .map_err(|e| anyhow!(e.to_string()))?
+            .get(user_id)
+        {
+            // sending only fails, when no one is subscribed. that's fine.
+            let _ = sender.send(cached_fronts);
+        }
+
+        Ok(())
+    }
+
+    pub fn get_cached_website_config(
+        &self,
+        user_id: &UserId,
+    ) -> Result<Option<Arc<users::UserConfigForUpdater>>> {
+        Ok(self
+            .website_configs
+            .lock()
+            .map_err(|e| anyhow!(e.to_string()))?
+            .get(user_id)
+            .cloned())
+    }
+
+    pub fn set_cached_website_config(
+        &self,
+        user_id: &UserId,
+        config: Arc<users::UserConfigForUpdater>,
+    ) -> Result<()> {
+        self.website_configs
+            .lock()
+            .map_err(|e| anyhow!(e.to_string()))?
+            .insert(user_id.to_owned(), config);
+
+        Ok(())
+    }
+
+    /// Must be called, whenever the stored config of the user changes.
+    pub fn invalidate_website_config(&self, user_id: &UserId) -> Result<()> {
+        self.website_configs
+            .lock()
+            .map_err(|e| anyhow!(e.to_string()))?
+            .remove(user_id);
+
+        Ok(())
+    }
+
+    pub fn subscribe_front_changes(
+        &self,
+        user_id: &UserId,
+    ) -> Result<broadcast::Receiver<CachedFronts>> {
+        Ok(self
+            .front_changes
+            .lock()
+            .map_err(|e| anyhow!(e.to_string()))?
+            .entry(user_id.to_owned())
+            .or_insert_with(|| broadcast::channel(FRONT_CHANGES_CHANNEL_CAPACITY).0)
+            .subscribe())
+    }
+
+    pub async fn record_updater_events(
+        &self,
+        user_id: &UserId,
+        events: Vec<database::UpdaterEvent>,
+    ) -> Result<()> {
+        let Some(db_pool) = &self.db_pool else {
+            return Ok(());
+        };
+
+        for event in &events {
+            database::insert_updater_event(db_pool, user_id, event).await?;
+        }
+
+        if !self.is_updater_events_prune_due(user_id, Utc::now())? {
+            return Ok(());
+        }
+
+        database::delete_updater_events_older_than(db_pool, user_id, UPDATER_EVENTS_RETENTION_DAYS)
+            .await
+    }
+
+    /// Whether the old updater events of the user are to be deleted now. If so, the next time is in a day.
+    #[allow(clippy::significant_drop_tightening)]
+    fn is_updater_events_prune_due(&self, user_id: &UserId, now: DateTime<Utc>) -> Result<bool> {
+        let mut pruned_at = self
+            .updater_events_pruned_at
+            .lock()
+            .map_err(|e| anyhow!(e.to_string()))?;
+
+        let due = pruned_at.get(user_id).is_none_or(|last| {
+            (now - *last).to_std().unwrap_or_default() >= UPDATER_EVENTS_PRUNE_INTERVAL
+        });
+        if due {
+            pruned_at.insert(user_id.to_owned(), now);
+        }
+
+        Ok(due)
+    }
+
+    /// Records fronts starting and ending by comparing the fetched fronts with the open history.
+    pub async fn record_front_history(
+        &self,
+        user_id: &UserId,
+        fronts: &[plurality::Fronter],
+    ) -> Result<()> {
+        let Some(db_pool) = &self.db_pool else {
+            return Ok(());
+        };
+
+        let open_entries = database::get_open_front_history_entries(db_pool, user_id).await?;
+        let transitions = plurality::front_transitions(&open_entries, fronts, Utc::now());
+
+        for ended in &transitions.ended {
+            database::close_front_history_entry(db_pool, user_id, ended.entry_id, ended.end_time)
+                .await?;
+        }
+
+        for started in &transitions.started {
+            database::insert_front_history_entry(
+                db_pool,
+                user_id,
+                &started.fronter_id,
+                &started.fronter_name,
+                started.start_time,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
     #[allow(clippy::significant_drop_tightening)]
     pub fn restart_updater(
         &self,
@@ -70,3 +320,69 @@ impl UpdaterManager {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cached_fronts_older_than_ttl_are_not_served() -> Result<()> {
+        let cli_args = setup::CliArgs {
+            website_fronts_cache_ttl: 60,
+            ..Default::default()
+        };
+        let manager = UpdaterManager::new(&cli_args, None);
+        let user_id = UserId::from(sqlx::types::Uuid::nil());
+
+        assert!(manager.get_fresh_cached_fronts(&user_id)?.is_none());
+
+        manager.set_cached_fronts(&user_id, CachedFronts::now(vec![]))?;
+        assert!(manager.get_fresh_cached_fronts(&user_id)?.is_some());
+
+        let stale = CachedFronts {
+            fronts: vec![],
+            fetched_at: Utc::now() - chrono::Duration::seconds(61),
+        };
+        manager.set_cached_fronts(&user_id, stale)?;
+        assert!(manager.get_fresh_cached_fronts(&user_id)?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_updater_events_are_pruned_at_startup_and_then_daily() -> Result<()> {
+        let manager = UpdaterManager::new(&setup::CliArgs::default(), None);
+        let user_id = UserId::from(sqlx::types::Uuid::nil());
+        let startup = Utc::now();
+
+        assert!(manager.is_updater_events_prune_due(&user_id, startup)?);
+        assert!(!manager
+            .is_updater_events_prune_due(&user_id, startup + chrono::Duration::hours(23))?);
+        assert!(
+            manager.is_updater_events_prune_due(&user_id, startup + chrono::Duration::hours(24))?
+        );
+        assert!(!manager
+            .is_updater_events_prune_due(&user_id, startup + chrono::Duration::hours(25))?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_only_changed_fronts_are_pushed_to_subscribers() -> Result<()> {
+        let manager = UpdaterManager::new(&setup::CliArgs::default(), None);
+        let user_id = UserId::from(sqlx::types::Uuid::nil());
+        let fronter = plurality::Fronter::for_tests("Alice");
+
+        let mut receiver = manager.subscribe_front_changes(&user_id)?;
+
+        manager.set_cached_fronts(&user_id, CachedFronts::now(vec![]))?;
+        manager.set_cached_fronts(&user_id, CachedFronts::now(vec![]))?;
+        manager.set_cached_fronts(&user_id, CachedFronts::now(vec![fronter.clone()]))?;
+
+        assert!(receiver.try_recv()?.fronts.is_empty());
+        assert_eq!(receiver.try_recv()?.fronts, vec![fronter]);
+        assert!(receiver.try_recv().is_err());
+
+        Ok(())
+    }
+}
@@ -1,7 +1,12 @@
 pub mod api;
+mod backoff;
+mod errors;
 mod manager;
 mod platforms;
+mod schedule;
 mod work_loop;
 
+pub use errors::*;
 pub use manager::*;
 pub use platforms::*;
+pub use work_loop::run_loop;
@@ -1,33 +1,161 @@
 use anyhow::Result;
-use serde::Serialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
+use crate::updater::{UpdaterError, UpdaterManager};
 use crate::{platforms, plurality, users};
 
-#[derive(Clone, Serialize, strum_macros::Display, Eq, Hash, PartialEq)]
+#[derive(
+    Clone,
+    Debug,
+    Serialize,
+    Deserialize,
+    strum_macros::Display,
+    Eq,
+    Hash,
+    PartialEq,
+    PartialOrd,
+    Ord,
+)]
 pub enum Platform {
     VRChat,
     DiscordStatusMessage,
+    DiscordNickname,
+    VRChatOsc,
 }
 
 #[derive(Clone, Serialize, strum_macros::Display)]
 pub enum UpdaterStatus {
     Inactive,
     Running,
-    Error(String),
+    Error(UpdaterError),
+}
+
+/// Overrides the time between two updates of the platform.
+/// It can't be lower than the minimum interval of the platform.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PlatformUpdateInterval {
+    pub platform: Platform,
+    pub seconds: u32,
+}
+
+/// Even if the status didn't change, it's pushed again after this interval.
+/// This overrides manual changes made directly on the platform.
+pub const FORCED_RESYNC_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Clone, Serialize)]
+pub struct UpdaterState {
+    pub status: UpdaterStatus,
+    pub last_pushed_status: Option<PushedStatus>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct PushedStatus {
+    pub value: String,
+    pub pushed_at: DateTime<Utc>,
+}
+
+impl PushedStatus {
+    pub fn now(value: String) -> Self {
+        Self {
+            value,
+            pushed_at: Utc::now(),
+        }
+    }
+
+    pub fn is_up_to_date(&self, value: &str) -> bool {
+        let resync_due = Utc::now()
+            .signed_duration_since(self.pushed_at)
+            .to_std()
+            .is_ok_and(|elapsed| elapsed >= FORCED_RESYNC_INTERVAL);
+
+        self.value == value && !resync_due
+    }
+}
+
+impl Platform {
+    /// Enforced by the server, as the platforms tolerate different amounts of updates.
+    pub const fn min_update_interval(&self) -> Duration {
+        match self {
+            Self::VRChat => Duration::from_secs(60),
+            Self::DiscordStatusMessage | Self::DiscordNickname => Duration::from_secs(30),
+            Self::VRChatOsc => Duration::from_secs(10),
+        }
+    }
+
+    /// The time between two updates. Defaults to `wait_seconds`, unless overridden for this platform.
+    pub fn update_interval(&self, config: &users::UserConfigForUpdater) -> Duration {
+        effective_update_interval(
+            self,
+            &config.platform_update_intervals,
+            config.wait_seconds.inner,
+        )
+    }
+
+    pub const fn enabled(&self, config: &users::UserConfigForUpdater) -> bool {
+        match self {
+            Self::VRChat => config.enable_vrchat,
+            Self::DiscordStatusMessage => config.enable_discord_status_message,
+            Self::DiscordNickname => config.enable_discord_nickname,
+            Self::VRChatOsc => config.enable_vrchat_osc,
+        }
+    }
+
+    /// The status string, which the updater of this platform pushes for the fronts.
+    pub fn format_status(
+        &self,
+        config: &users::UserConfigForUpdater,
+        fronts: &[plurality::Fronter],
+    ) -> String {
+        match self {
+            Self::VRChat => platforms::format_vrchat_status(config, fronts),
+            Self::DiscordStatusMessage => platforms::format_discord_status(config, fronts),
+            // the nickname in the first configured server
+            Self::DiscordNickname => config
+                .discord_nickname_guilds
+                .first()
+                .map(|g| platforms::format_discord_nickname(config, &g.template, fronts))
+                .unwrap_or_default(),
+            Self::VRChatOsc => platforms::format_vrchat_chatbox(config, fronts),
+        }
+    }
+}
+
+fn effective_update_interval(
+    platform: &Platform,
+    overrides: &[PlatformUpdateInterval],
+    default: Duration,
+) -> Duration {
+    overrides
+        .iter()
+        .find(|o| &o.platform == platform)
+        .map_or(default, |o| Duration::from_secs(o.seconds.into()))
+        .max(platform.min_update_interval())
 }
 
 pub enum Updater {
     VRChat(Box<platforms::VRChatUpdater>),
     DiscordStatusMessage(platforms::DiscordStatusMessageUpdater),
+    DiscordNickname(platforms::DiscordNicknameUpdater),
+    VRChatOsc(platforms::VRChatOscUpdater),
 }
 
-pub fn available_updaters(discord_status_message: bool) -> Vec<Platform> {
+pub fn available_updaters(shared_updaters: &UpdaterManager) -> Vec<Platform> {
     let mut platforms = vec![Platform::VRChat];
 
-    if discord_status_message {
+    if shared_updaters.discord_status_message_available {
         platforms.push(Platform::DiscordStatusMessage);
     }
 
+    if shared_updaters.discord_bot_token.is_some() {
+        platforms.push(Platform::DiscordNickname);
+    }
+
+    if shared_updaters.vrchat_osc_available {
+        platforms.push(Platform::VRChatOsc);
+    }
+
     for p in platforms.iter().by_ref() {
         eprintln!("Available platform: {p}");
     }
@@ -36,12 +164,23 @@ pub fn available_updaters(discord_status_message: bool) -> Vec<Platform> {
 }
 
 impl Updater {
-    pub fn new(platform: Platform) -> Self {
+    pub fn new(platform: Platform, shared_updaters: &UpdaterManager) -> Self {
         match platform {
-            Platform::VRChat => Self::VRChat(Box::new(platforms::VRChatUpdater::new(platform))),
+            Platform::VRChat => Self::VRChat(Box::new(platforms::VRChatUpdater::new(
+                platform,
+                shared_updaters,
+            ))),
             Platform::DiscordStatusMessage => {
                 Self::DiscordStatusMessage(platforms::DiscordStatusMessageUpdater::new(platform))
             }
+            Platform::DiscordNickname => {
+                Self::DiscordNickname(platforms::DiscordNicknameUpdater::new(
+                    platform,
+                    shared_updaters.discord_bot_token.as_deref(),
+                    shared_updaters.db_pool.clone(),
+                ))
+            }
+            Platform::VRChatOsc => Self::VRChatOsc(platforms::VRChatOscUpdater::new(platform)),
         }
     }
 
@@ -49,6 +188,8 @@ impl Updater {
         match self {
             Self::VRChat(_) => Platform::VRChat,
             Self::DiscordStatusMessage(_) => Platform::DiscordStatusMessage,
+            Self::DiscordNickname(_) => Platform::DiscordNickname,
+            Self::VRChatOsc(_) => Platform::VRChatOsc,
         }
     }
 
@@ -61,24 +202,59 @@ impl Updater {
         }
     }
 
-    pub const fn last_operation_error(&self) -> Option<&String> {
+    pub fn state(&self, config: &users::UserConfigForUpdater) -> UpdaterState {
+        UpdaterState {
+            status: self.status(config),
+            last_pushed_status: self.last_pushed_status().cloned(),
+        }
+    }
+
+    pub const fn last_pushed_status(&self) -> Option<&PushedStatus> {
+        match self {
+            Self::VRChat(updater) => updater.last_pushed_status.as_ref(),
+            Self::DiscordStatusMessage(updater) => updater.last_pushed_status.as_ref(),
+            Self::DiscordNickname(updater) => updater.last_pushed_status.as_ref(),
+            Self::VRChatOsc(updater) => updater.last_pushed_status.as_ref(),
+        }
+    }
+
+    pub const fn last_operation_error(&self) -> Option<&UpdaterError> {
         match self {
             Self::VRChat(updater) => updater.last_operation_error.as_ref(),
             Self::DiscordStatusMessage(updater) => updater.last_operation_error.as_ref(),
+            Self::DiscordNickname(updater) => updater.last_operation_error.as_ref(),
+            Self::VRChatOsc(updater) => updater.last_operation_error.as_ref(),
         }
     }
 
-    pub const fn enabled(&self, config: &users::UserConfigForUpdater) -> bool {
+    const fn last_operation_error_mut(&mut self) -> &mut Option<UpdaterError> {
         match self {
-            Self::VRChat(_) => config.enable_vrchat,
-            Self::DiscordStatusMessage(_) => config.enable_discord_status_message,
+            Self::VRChat(updater) => &mut updater.last_operation_error,
+            Self::DiscordStatusMessage(updater) => &mut updater.last_operation_error,
+            Self::DiscordNickname(updater) => &mut updater.last_operation_error,
+            Self::VRChatOsc(updater) => &mut updater.last_operation_error,
         }
     }
 
+    /// The updater couldn't run, because the fronts couldn't be fetched.
+    pub fn record_fronts_fetch_error(&mut self, error: &anyhow::Error) {
+        let last_operation_error = self.last_operation_error_mut();
+        *last_operation_error = Some(UpdaterError::record_fronts_fetch(
+            last_operation_error.take(),
+            error,
+        ));
+    }
+
+    pub const fn enabled(&self, config: &users::UserConfigForUpdater) -> bool {
+        self.platform().enabled(config)
+    }
+
     pub async fn setup(&mut self, config: &users::UserConfigForUpdater) -> Result<()> {
         match self {
             Self::VRChat(updater) => updater.setup(config).await,
             Self::DiscordStatusMessage(updater) => updater.setup(config).await,
+            Self::DiscordNickname(updater) => updater.setup(config).await,
+            Self::VRChatOsc(updater) => updater.setup(config).await,
         }
     }
 
@@ -92,6 +268,59 @@ impl Updater {
             Self::DiscordStatusMessage(updater) => {
                 updater.update_fronting_status(config, fronts).await
             }
+            Self::DiscordNickname(updater) => updater.update_fronting_status(config, fronts).await,
+            Self::VRChatOsc(updater) => updater.update_fronting_status(config, fronts).await,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pushed_status_is_up_to_date_only_for_same_recent_value() {
+        let pushed = PushedStatus::now("F: Alice".to_owned());
+
+        assert!(pushed.is_up_to_date("F: Alice"));
+        assert!(!pushed.is_up_to_date("F: Bob"));
+    }
+
+    #[test]
+    fn test_update_interval_is_overridden_per_platform_but_not_below_minimum() {
+        let overrides = vec![
+            PlatformUpdateInterval {
+                platform: Platform::DiscordStatusMessage,
+                seconds: 300,
+            },
+            PlatformUpdateInterval {
+                platform: Platform::VRChat,
+                seconds: 5,
+            },
+        ];
+        let default = Duration::from_secs(45);
+
+        assert_eq!(
+            effective_update_interval(&Platform::DiscordStatusMessage, &overrides, default),
+            Duration::from_secs(300)
+        );
+        assert_eq!(
+            effective_update_interval(&Platform::VRChat, &overrides, default),
+            Platform::VRChat.min_update_interval()
+        );
+        assert_eq!(
+            effective_update_interval(&Platform::DiscordNickname, &overrides, default),
+            default
+        );
+    }
+
+    #[test]
+    fn test_pushed_status_is_resynced_after_interval() {
+        let pushed = PushedStatus {
+            value: "F: Alice".to_owned(),
+            pushed_at: Utc::now() - FORCED_RESYNC_INTERVAL,
+        };
+
+        assert!(!pushed.is_up_to_date("F: Alice"));
+    }
+}
@@ -0,0 +1,114 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::time::Instant;
+
+use crate::updater::Platform;
+
+/// The next update of each platform, earliest first.
+#[derive(Default)]
+pub struct Schedule {
+    queue: BinaryHeap<Reverse<(Instant, Platform)>>,
+    last_updates: HashMap<Platform, Instant>,
+}
+
+impl Schedule {
+    pub fn insert(&mut self, platform: Platform, at: Instant) {
+        self.queue.push(Reverse((at, platform)));
+    }
+
+    pub fn next_due(&self) -> Option<Instant> {
+        self.queue.peek().map(|Reverse((at, _))| *at)
+    }
+
+    /// Removes the platforms, which are due now. They need to be inserted again for their next update.
+    pub fn pop_due(&mut self, now: Instant) -> Vec<Platform> {
+        let mut due = vec![];
+
+        while let Some(Reverse((at, _))) = self.queue.peek() {
+            if *at > now {
+                break;
+            }
+            if let Some(Reverse((_, platform))) = self.queue.pop() {
+                self.last_updates.insert(platform.clone(), now);
+                due.push(platform);
+            }
+        }
+
+        due
+    }
+
+    /// Moves the next updates forward, e.g. when the fronts changed,
+    /// but not earlier than the minimum interval of each platform allows.
+    pub fn advance(&mut self, now: Instant, may_advance: impl Fn(&Platform) -> bool) {
+        self.queue = self
+            .queue
+            .drain()
+            .map(|Reverse((at, platform))| {
+                if !may_advance(&platform) {
+                    return Reverse((at, platform));
+                }
+                let earliest = self.last_updates.get(&platform).map_or(now, |last| {
+                    (*last + platform.min_update_interval()).max(now)
+                });
+                Reverse((at.min(earliest), platform))
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_platforms_are_due_in_order_of_their_next_update() {
+        let mut schedule = Schedule::default();
+        let now = Instant::now();
+
+        schedule.insert(Platform::VRChat, now + Duration::from_secs(120));
+        schedule.insert(
+            Platform::DiscordStatusMessage,
+            now + Duration::from_secs(30),
+        );
+        schedule.insert(Platform::VRChatOsc, now);
+
+        assert_eq!(schedule.next_due(), Some(now));
+        assert_eq!(schedule.pop_due(now), vec![Platform::VRChatOsc]);
+        assert_eq!(schedule.next_due(), Some(now + Duration::from_secs(30)));
+        assert_eq!(
+            schedule.pop_due(now + Duration::from_secs(200)),
+            vec![Platform::DiscordStatusMessage, Platform::VRChat]
+        );
+        assert_eq!(schedule.next_due(), None);
+    }
+
+    #[test]
+    fn test_front_changes_advance_updates_only_down_to_minimum_interval() {
+        let mut schedule = Schedule::default();
+        let start = Instant::now();
+
+        schedule.insert(Platform::VRChat, start);
+        schedule.insert(Platform::DiscordStatusMessage, start);
+        let due = schedule.pop_due(start);
+        assert_eq!(due.len(), 2);
+
+        let in_ten_minutes = start + Duration::from_secs(600);
+        schedule.insert(Platform::VRChat, in_ten_minutes);
+        schedule.insert(Platform::DiscordStatusMessage, in_ten_minutes);
+
+        // e.g. discord is backing off
+        let changed_at = start + Duration::from_secs(5);
+        schedule.advance(changed_at, |p| p != &Platform::DiscordStatusMessage);
+
+        assert_eq!(
+            schedule.next_due(),
+            Some(start + Platform::VRChat.min_update_interval())
+        );
+        assert_eq!(
+            schedule.pop_due(start + Duration::from_secs(599)),
+            vec![Platform::VRChat]
+        );
+        assert_eq!(schedule.next_due(), Some(in_ten_minutes));
+    }
+}
@@ -1,14 +1,19 @@
 use std::collections::HashMap;
-use tokio::time::sleep;
+use std::time::Instant;
+use tokio::time::sleep_until;
 
-use crate::updater::platforms::{Platform, Updater, UpdaterStatus};
+use crate::database;
+use crate::plurality::{self, FrontChangeListener};
+use crate::updater::backoff::Backoffs;
+use crate::updater::platforms::{Platform, Updater, UpdaterState};
+use crate::updater::schedule::Schedule;
 use crate::updater::{manager, platforms};
-use crate::{plurality, users};
+use crate::users;
 use anyhow::Result;
 use chrono::Utc;
 
 pub type CancleableUpdater = tokio::task::JoinHandle<()>;
-pub type UserUpdatersStatuses = HashMap<Platform, UpdaterStatus>;
+pub type UserUpdatersStatuses = HashMap<Platform, UpdaterState>;
 type UserUpdaters = HashMap<Platform, Updater>;
 
 pub async fn run_loop(
@@ -17,15 +22,29 @@ pub async fn run_loop(
 ) -> ! {
     eprintln!("Running Updater ...");
 
-    let mut updaters: UserUpdaters =
-        platforms::available_updaters(shared_updaters.discord_status_message_available)
-            .iter()
-            .map(|platform| (platform.to_owned(), Updater::new(platform.to_owned())))
-            .collect();
+    let mut updaters: UserUpdaters = platforms::available_updaters(&shared_updaters)
+        .iter()
+        .map(|platform| {
+            (
+                platform.to_owned(),
+                Updater::new(platform.to_owned(), &shared_updaters),
+            )
+        })
+        .collect();
+
+    // reset on restarts, i.e. platforms paused due to e.g. invalid credentials are retried with the new config
+    let mut backoffs = Backoffs::default();
+    let mut schedule = Schedule::default();
 
     for u in updaters.values_mut() {
         if u.enabled(&config) {
-            log_error_and_continue(&u.platform().to_string(), u.setup(&config).await);
+            let platform = u.platform();
+            let result = u.setup(&config).await;
+            let now = Instant::now();
+            backoffs.record(&platform, &result, platform.update_interval(&config), now);
+            log_error_and_continue(&platform.to_string(), result);
+            // the first update happens right away
+            schedule_next_update(&mut schedule, &backoffs, platform, now);
         }
     }
 
@@ -35,13 +54,42 @@ pub async fn run_loop(
         shared_updaters.set_updater_state(&config.user_id, statues),
     );
 
+    // Fronts changes are pushed via websocket, if the source supports it.
+    // The regular polling then only remains as a fallback heartbeat.
+    let mut front_changes = match config.fronting_source {
+        plurality::FrontingSource::SimplyPlural => {
+            Some(plurality::SimplyPluralWebsocket::new(&config))
+        }
+        plurality::FrontingSource::PluralKit => None,
+    };
+
     loop {
+        let now = Instant::now();
+        let due = schedule.pop_due(now);
+
         eprintln!(
             "\n\n======================= UTC {}",
             Utc::now().format("%Y-%m-%d %H:%M:%S")
         );
 
-        log_error_and_continue("Updater Logic", loop_logic(&config, &mut updaters).await);
+        // the fronts are fetched on every trigger, as the website and its event stream use them as well
+        log_error_and_continue(
+            "Updater Logic",
+            loop_logic(
+                &config,
+                &mut updaters,
+                &due,
+                &mut backoffs,
+                &shared_updaters,
+                now,
+            )
+            .await,
+        );
+
+        for platform in due {
+            let normally_at = now + platform.update_interval(&config);
+            schedule_next_update(&mut schedule, &backoffs, platform, normally_at);
+        }
 
         let statues = get_statuses(&updaters, &config);
         log_error_and_continue(
@@ -49,12 +97,55 @@ pub async fn run_loop(
             shared_updaters.set_updater_state(&config.user_id, statues),
         );
 
+        // the fronts are fetched at least every wait_seconds, even if no platform is due
+        let heartbeat = Instant::now() + config.wait_seconds.inner;
+        let next_update = schedule
+            .next_due()
+            .map_or(heartbeat, |at| at.min(heartbeat));
+
         eprintln!(
             "Waiting {}s for next update trigger...",
-            config.wait_seconds.inner.as_secs()
+            next_update
+                .saturating_duration_since(Instant::now())
+                .as_secs()
         );
 
-        sleep(config.wait_seconds.inner).await;
+        let fronts_changed =
+            wait_for_next_update_trigger(next_update, front_changes.as_mut()).await;
+
+        if fronts_changed {
+            schedule.advance(Instant::now(), |p| !backoffs.is_backing_off(p));
+        }
+    }
+}
+
+fn schedule_next_update(
+    schedule: &mut Schedule,
+    backoffs: &Backoffs,
+    platform: Platform,
+    normally_at: Instant,
+) {
+    match backoffs.next_attempt(&platform, normally_at) {
+        Some(at) => schedule.insert(platform, at),
+        None => eprintln!("{platform} is paused until the config changes."),
+    }
+}
+
+/// Returns true, if woken up due to a front change.
+async fn wait_for_next_update_trigger<L: FrontChangeListener>(
+    next_update: Instant,
+    front_changes: Option<&mut L>,
+) -> bool {
+    let deadline = tokio::time::Instant::from_std(next_update);
+
+    if let Some(listener) = front_changes {
+        tokio::select! {
+            () = sleep_until(deadline) => false,
+            () = listener.wait_for_front_change() => true,
+        }
+    } else {
+        sleep_until(deadline).await;
+        false
     }
 }
 
@@ -64,28 +155,107 @@ fn get_statuses(
 ) -> UserUpdatersStatuses {
     updaters
         .iter()
-        .map(|(k, u)| (k.to_owned(), u.status(config)))
+        .map(|(k, u)| (k.to_owned(), u.state(config)))
         .collect()
 }
 
 async fn loop_logic(
     config: &users::UserConfigForUpdater,
     updaters: &mut UserUpdaters,
+    due: &[Platform],
+    backoffs: &mut Backoffs,
+    shared_updaters: &manager::UpdaterManager,
+    now: Instant,
 ) -> Result<()> {
-    let fronts = plurality::fetch_fronts(config).await?;
+    // the platforms keep showing the error of the last fetch meanwhile
+    if !backoffs.is_fronts_fetch_due(now) {
+        eprintln!("Fetching fronts is backing off or paused. Skipping updates.");
+        return Ok(());
+    }
 
-    for updater in updaters.values_mut() {
-        if updater.enabled(config) {
+    let fronts = plurality::fetch_fronts(config).await;
+    backoffs.record_fronts_fetch(&fronts, config.wait_seconds.inner, now);
+
+    let fronts = match fronts {
+        Ok(fronts) => fronts,
+        Err(err) => {
+            let events = updaters
+                .values_mut()
+                .filter(|u| due.contains(&u.platform()) && u.enabled(config))
+                .map(|u| {
+                    u.record_fronts_fetch_error(&err);
+                    updater_event(u, config, &[], Some(&err.to_string()))
+                })
+                .collect();
             log_error_and_continue(
-                &updater.platform().to_string(),
-                updater.update_fronting_status(config, &fronts).await,
+                "record updater events",
+                shared_updaters
+                    .record_updater_events(&config.user_id, events)
+                    .await,
             );
+            return Err(err);
         }
+    };
+
+    log_error_and_continue(
+        "cache fronts",
+        shared_updaters
+            .set_cached_fronts(&config.user_id, manager::CachedFronts::now(fronts.clone())),
+    );
+
+    log_error_and_continue(
+        "record front history",
+        shared_updaters
+            .record_front_history(&config.user_id, &fronts)
+            .await,
+    );
+
+    let mut events = vec![];
+
+    for platform in due {
+        let Some(updater) = updaters.get_mut(platform) else {
+            continue;
+        };
+        if updater.enabled(config) {
+            let result = updater.update_fronting_status(config, &fronts).await;
+            backoffs.record(platform, &result, platform.update_interval(config), now);
+            let error = result.as_ref().err().map(ToString::to_string);
+            events.push(updater_event(updater, config, &fronts, error.as_ref()));
+            log_error_and_continue(&updater.platform().to_string(), result);
+        }
+    }
+
+    if !events.is_empty() {
+        log_error_and_continue(
+            "record updater events",
+            shared_updaters
+                .record_updater_events(&config.user_id, events)
+                .await,
+        );
     }
 
     Ok(())
 }
 
+// the status of this update, as a failed update may not have pushed anything
+fn updater_event(
+    updater: &Updater,
+    config: &users::UserConfigForUpdater,
+    fronts: &[plurality::Fronter],
+    error: Option<&String>,
+) -> database::UpdaterEvent {
+    database::UpdaterEvent {
+        platform: updater.platform().to_string(),
+        fronters: fronts.iter().map(|f| f.name.clone()).collect(),
+        status_string: error
+            .is_none()
+            .then(|| updater.platform().format_status(config, fronts)),
+        success: error.is_none(),
+        error: error.cloned(),
+        created_at: Utc::now(),
+    }
+}
+
 fn log_error_and_continue(loop_part_name: &str, res: Result<()>) {
     match res {
         core::result::Result::Ok(()) => {}
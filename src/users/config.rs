@@ -2,7 +2,9 @@ use anyhow::{anyhow, Result};
 use sqlx::FromRow;
 use std::time::Duration;
 
-use crate::{config_value, config_value_if, database, users::model::UserId};
+use crate::{
+    config_value, config_value_if, database, platforms, plurality, updater, users::model::UserId,
+};
 use serde::{Deserialize, Serialize};
 
 use sp2any_macros::WithOptionDefaults;
@@ -21,21 +23,62 @@ where
     // None: Use default value, if available
     // Some(x): Use this value
     pub wait_seconds: Option<i32>,
+    // overrides of wait_seconds for single platforms
+    #[sqlx(json(nullable))]
+    pub platform_update_intervals: Option<Vec<updater::PlatformUpdateInterval>>,
 
     pub system_name: Option<String>,
 
+    pub fronting_source: Option<plurality::FrontingSource>,
+
     pub status_prefix: Option<String>,
     pub status_no_fronts: Option<String>,
     pub status_truncate_names_to: Option<i32>,
+    pub status_templates_vrchat: Option<Vec<String>>,
+    pub status_templates_discord_status_message: Option<Vec<String>>,
+
+    // member or custom front IDs or names, which are never shown on the website
+    pub website_hidden_fronters: Option<Vec<String>>,
+    pub website_theme: Option<platforms::WebsiteTheme>,
+    pub website_layout: Option<platforms::WebsiteLayout>,
+    pub website_show_avatars: Option<bool>,
+    pub website_show_member_colors: Option<bool>,
+    pub website_custom_css: Option<String>,
+
+    // servers, in which the discord bot sets the nickname of the linked discord account
+    #[sqlx(json(nullable))]
+    pub discord_nickname_guilds: Option<Vec<platforms::DiscordNicknameGuild>>,
+
+    // where VRChat listens for OSC messages. only reachable when running locally.
+    pub vrchat_osc_host: Option<String>,
+    pub vrchat_osc_port: Option<i32>,
+    // bool avatar parameters, which are true while the fronter is fronting
+    #[sqlx(json(nullable))]
+    pub vrchat_osc_avatar_parameters: Option<Vec<platforms::VRChatOscAvatarParameter>>,
+
+    // the avatar, which is selected in VRChat while the fronter is fronting
+    #[sqlx(json(nullable))]
+    pub vrchat_avatars: Option<Vec<platforms::VRChatAvatar>>,
+    pub vrchat_avatar_cofront_policy: Option<platforms::VRChatAvatarCofrontPolicy>,
+    // template of the fronting section in the VRChat bio. empty: the bio is left untouched.
+    pub vrchat_bio_template: Option<String>,
+    #[sqlx(json(nullable))]
+    pub vrchat_status_states: Option<Vec<platforms::VRChatStatusStateMapping>>,
 
     pub enable_discord_status_message: Option<bool>,
     pub enable_vrchat: Option<bool>,
+    pub enable_website: Option<bool>,
+    pub enable_discord_nickname: Option<bool>,
+    pub enable_vrchat_osc: Option<bool>,
 
     pub simply_plural_token: Option<Secret>,
+    pub pluralkit_token: Option<Secret>,
     pub discord_status_message_token: Option<Secret>,
     pub vrchat_username: Option<Secret>,
     pub vrchat_password: Option<Secret>,
     pub vrchat_cookie: Option<Secret>,
+    // when set, the website is only shown with `?token=<website_access_token>`
+    pub website_access_token: Option<Secret>,
 }
 
 pub fn default_user_db_entries<S: database::SecretType>() -> UserConfigDbEntries<S> {
@@ -43,35 +86,89 @@ pub fn default_user_db_entries<S: database::SecretType>() -> UserConfigDbEntries
         status_prefix: Some(String::from("F:")),
         status_no_fronts: Some(String::from("none?")),
         status_truncate_names_to: Some(3),
+        status_templates_vrchat: Some(plurality::default_status_templates_strings()),
+        status_templates_discord_status_message: Some(plurality::default_status_templates_strings()),
         wait_seconds: Some(60),
+        platform_update_intervals: Some(vec![]),
+        fronting_source: Some(plurality::FrontingSource::SimplyPlural),
         enable_discord_status_message: Some(false),
         enable_vrchat: Some(false),
+        enable_website: Some(false),
+        enable_discord_nickname: Some(false),
+        discord_nickname_guilds: Some(vec![]),
+        enable_vrchat_osc: Some(false),
+        vrchat_osc_host: Some(String::from("127.0.0.1")),
+        vrchat_osc_port: Some(9000),
+        vrchat_osc_avatar_parameters: Some(vec![]),
+        vrchat_avatars: Some(vec![]),
+        vrchat_avatar_cofront_policy: Some(platforms::VRChatAvatarCofrontPolicy::First),
+        vrchat_bio_template: Some(String::new()),
+        vrchat_status_states: Some(vec![]),
+        website_hidden_fronters: Some(vec![]),
+        website_theme: Some(platforms::WebsiteTheme::Light),
+        website_layout: Some(platforms::WebsiteLayout::List),
+        website_show_avatars: Some(true),
+        website_show_member_colors: Some(false),
+        website_custom_css: Some(String::new()),
         ..Default::default()
     }
 }
 
+#[allow(clippy::struct_excessive_bools)]
 pub struct UserConfigForUpdater {
     pub client: reqwest::Client,
     pub user_id: UserId,
     pub simply_plural_base_url: String,
+    pub simply_plural_websocket_url: String,
+    pub pluralkit_base_url: String,
     pub discord_base_url: String,
 
     // Note: v Keep this in sync with UserConfigDbEntries! v
     pub wait_seconds: WaitSeconds,
+    pub platform_update_intervals: Vec<updater::PlatformUpdateInterval>,
 
     pub system_name: String,
+
+    pub fronting_source: plurality::FrontingSource,
+
     pub status_prefix: String,
     pub status_no_fronts: String,
     pub status_truncate_names_to: usize,
+    pub status_templates_vrchat: Vec<plurality::StatusTemplate>,
+    pub status_templates_discord_status_message: Vec<plurality::StatusTemplate>,
+
+    pub website_hidden_fronters: Vec<String>,
+    pub website_theme: platforms::WebsiteTheme,
+    pub website_layout: platforms::WebsiteLayout,
+    pub website_show_avatars: bool,
+    pub website_show_member_colors: bool,
+    // validated and safe to embed
+    pub website_custom_css: String,
+
+    pub discord_nickname_guilds: Vec<platforms::DiscordNicknameTemplate>,
+
+    pub vrchat_osc_host: String,
+    pub vrchat_osc_port: u16,
+    pub vrchat_osc_avatar_parameters: Vec<platforms::VRChatOscAvatarParameter>,
+
+    pub vrchat_avatars: Vec<platforms::VRChatAvatar>,
+    pub vrchat_avatar_cofront_policy: platforms::VRChatAvatarCofrontPolicy,
+    pub vrchat_bio_template: Option<plurality::StatusTemplate>,
+    pub vrchat_status_states: Vec<platforms::VRChatStatusStateMapping>,
 
     pub enable_discord_status_message: bool,
     pub enable_vrchat: bool,
+    pub enable_website: bool,
+    pub enable_discord_nickname: bool,
+    pub enable_vrchat_osc: bool,
 
     pub simply_plural_token: database::Decrypted,
+    pub pluralkit_token: database::Decrypted,
     pub discord_status_message_token: database::Decrypted,
     pub vrchat_username: database::Decrypted,
     pub vrchat_password: database::Decrypted,
     pub vrchat_cookie: database::Decrypted,
+    pub website_access_token: database::Decrypted,
 }
 
 #[derive(Clone, Deserialize, Serialize, Debug, Default)]
@@ -92,6 +189,7 @@ impl From<i32> for WaitSeconds {
     }
 }
 
+#[allow(clippy::too_many_lines)]
 pub fn create_config_with_strong_constraints<Constraints>(
     user_id: &UserId,
     client: &reqwest::Client,
@@ -112,15 +210,83 @@ where
         config_value!(local_config_with_defaults, enable_discord_status_message)?;
     let enable_vrchat = config_value!(local_config_with_defaults, enable_vrchat)?;
 
+    let fronting_source = config_value!(local_config_with_defaults, fronting_source)?;
+    let uses_simply_plural = fronting_source == plurality::FrontingSource::SimplyPlural;
+    let uses_pluralkit = fronting_source == plurality::FrontingSource::PluralKit;
+
     let config = UserConfigForUpdater {
         user_id: user_id.clone(),
         client: client.clone(),
         wait_seconds: config_value!(local_config_with_defaults, wait_seconds)?.into(),
+        platform_update_intervals: config_value!(
+            local_config_with_defaults,
+            platform_update_intervals
+        )?,
         system_name: config_value!(local_config_with_defaults, system_name)?,
-        simply_plural_token: config_value!(local_config_with_defaults, simply_plural_token)?,
+        fronting_source,
+        simply_plural_token: config_value_if!(
+            uses_simply_plural,
+            local_config_with_defaults,
+            simply_plural_token
+        )?,
         simply_plural_base_url: String::from("https://api.apparyllis.com/v1"),
+        simply_plural_websocket_url: String::from("wss://api.apparyllis.com/v1/socket"),
+        pluralkit_token: config_value_if!(
+            uses_pluralkit,
+            local_config_with_defaults,
+            pluralkit_token
+        )?,
+        pluralkit_base_url: String::from("https://api.pluralkit.me/v2"),
         enable_discord_status_message,
         enable_vrchat,
+        enable_website: config_value!(local_config_with_defaults, enable_website)?,
+        enable_discord_nickname: config_value!(
+            local_config_with_defaults,
+            enable_discord_nickname
+        )?,
+        discord_nickname_guilds: platforms::parse_discord_nickname_guilds(&config_value!(
+            local_config_with_defaults,
+            discord_nickname_guilds
+        )?)?,
+        enable_vrchat_osc: config_value!(local_config_with_defaults, enable_vrchat_osc)?,
+        vrchat_osc_host: config_value!(local_config_with_defaults, vrchat_osc_host)?,
+        vrchat_osc_port: config_value!(local_config_with_defaults, vrchat_osc_port)?.try_into()?,
+        vrchat_osc_avatar_parameters: platforms::validate_vrchat_osc_avatar_parameters(
+            config_value!(local_config_with_defaults, vrchat_osc_avatar_parameters)?,
+        )?,
+        vrchat_avatars: platforms::validate_vrchat_avatars(config_value!(
+            local_config_with_defaults,
+            vrchat_avatars
+        )?)?,
+        vrchat_avatar_cofront_policy: config_value!(
+            local_config_with_defaults,
+            vrchat_avatar_cofront_policy
+        )?,
+        vrchat_bio_template: Some(config_value!(
+            local_config_with_defaults,
+            vrchat_bio_template
+        )?)
+        .filter(|t| !t.trim().is_empty())
+        .map(|t| plurality::StatusTemplate::parse(&t))
+        .transpose()?,
+        vrchat_status_states: config_value!(local_config_with_defaults, vrchat_status_states)?,
+        website_hidden_fronters: config_value!(
+            local_config_with_defaults,
+            website_hidden_fronters
+        )?,
+        website_theme: config_value!(local_config_with_defaults, website_theme)?,
+        website_layout: config_value!(local_config_with_defaults, website_layout)?,
+        website_show_avatars: config_value!(local_config_with_defaults, website_show_avatars)?,
+        website_show_member_colors: config_value!(
+            local_config_with_defaults,
+            website_show_member_colors
+        )?,
+        website_custom_css: platforms::validate_custom_css(&config_value!(
+            local_config_with_defaults,
+            website_custom_css
+        )?)?,
+        website_access_token: config_value!(local_config_with_defaults, website_access_token)
+            .unwrap_or_default(),
         discord_base_url: if enable_discord_status_message {
             String::from("https://discord.com")
         } else {
@@ -148,6 +314,16 @@ where
             status_truncate_names_to
         )?
         .try_into()?,
+        status_templates_vrchat: plurality::parse_status_templates(&config_value!(
+            local_config_with_defaults,
+            status_templates_vrchat
+        )?)?,
+        status_templates_discord_status_message: plurality::parse_status_templates(
+            &config_value!(
+                local_config_with_defaults,
+                status_templates_discord_status_message
+            )?,
+        )?,
         vrchat_cookie: config_value!(local_config_with_defaults, vrchat_cookie)
             .inspect(|_| eprintln!("A VRChat cookie was found and will be used."))
             .unwrap_or_default(),
@@ -168,6 +344,25 @@ where
     Ok((config, valid_config))
 }
 
+/// A valid config of the nil user, which fetches the fronts from Simply Plural.
+/// The given entries override the minimal defaults.
+#[cfg(test)]
+pub fn config_for_tests(
+    db_config: &UserConfigDbEntries<database::Decrypted>,
+) -> Result<UserConfigForUpdater> {
+    let db_config = db_config.with_option_defaults(UserConfigDbEntries {
+        system_name: Some("System".to_owned()),
+        simply_plural_token: Some("sp-token".into()),
+        ..Default::default()
+    });
+    let (config, _) = create_config_with_strong_constraints(
+        &UserId::from(sqlx::types::Uuid::nil()),
+        &reqwest::Client::new(),
+        &db_config,
+    )?;
+    Ok(config)
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
@@ -175,45 +370,126 @@ mod tests {
     use crate::database::Decrypted;
 
     #[test]
+    #[allow(clippy::too_many_lines)]
     fn test_user_config_db_entries_serialization() {
         let config = UserConfigDbEntries::<Decrypted> {
             wait_seconds: Some(30),
+            platform_update_intervals: Some(vec![updater::PlatformUpdateInterval {
+                platform: updater::Platform::VRChat,
+                seconds: 120,
+            }]),
             system_name: Some("My System".to_string()),
+            fronting_source: Some(plurality::FrontingSource::SimplyPlural),
             status_prefix: Some("SP:".to_string()),
             status_no_fronts: Some("No one fronting".to_string()),
             status_truncate_names_to: Some(5),
+            status_templates_vrchat: None,
+            status_templates_discord_status_message: None,
+            website_hidden_fronters: Some(vec!["Secret Member".to_string()]),
+            website_theme: Some(platforms::WebsiteTheme::Transparent),
+            website_layout: Some(platforms::WebsiteLayout::Compact),
+            website_show_avatars: Some(false),
+            website_show_member_colors: None,
+            website_custom_css: None,
+            discord_nickname_guilds: Some(vec![platforms::DiscordNicknameGuild {
+                guild_id: "1234".to_string(),
+                template: "{names} | The Crew".to_string(),
+            }]),
+            vrchat_osc_host: None,
+            vrchat_osc_port: Some(9001),
+            vrchat_osc_avatar_parameters: None,
+            vrchat_avatars: Some(vec![platforms::VRChatAvatar {
+                fronter: "Alice".to_string(),
+                avatar_id: "avtr_1234".to_string(),
+            }]),
+            vrchat_avatar_cofront_policy: Some(platforms::VRChatAvatarCofrontPolicy::Longest),
+            vrchat_bio_template: Some("Fronting: {names}".to_string()),
+            vrchat_status_states: Some(vec![platforms::VRChatStatusStateMapping {
+                fronter: "Sleeping".to_string(),
+                state: platforms::VRChatStatusState::Busy,
+            }]),
             enable_discord_status_message: Some(true),
             enable_vrchat: Some(false),
+            enable_website: Some(true),
+            enable_discord_nickname: None,
+            enable_vrchat_osc: Some(true),
             simply_plural_token: Some(Decrypted {
                 secret: "sp_token_123".to_string(),
             }),
+            pluralkit_token: None,
             discord_status_message_token: Some(Decrypted {
                 secret: "discord_status_message_token_abc".to_string(),
             }),
             vrchat_username: None,
             vrchat_password: None,
             vrchat_cookie: None,
+            website_access_token: None,
             valid_constraints: None,
         };
 
         let json_string = serde_json::to_string_pretty(&config).unwrap();
         let expected_json = r#"{
   "wait_seconds": 30,
+  "platform_update_intervals": [
+    {
+      "platform": "VRChat",
+      "seconds": 120
+    }
+  ],
   "system_name": "My System",
+  "fronting_source": "SimplyPlural",
   "status_prefix": "SP:",
   "status_no_fronts": "No one fronting",
   "status_truncate_names_to": 5,
+  "status_templates_vrchat": null,
+  "status_templates_discord_status_message": null,
+  "website_hidden_fronters": [
+    "Secret Member"
+  ],
+  "website_theme": "Transparent",
+  "website_layout": "Compact",
+  "website_show_avatars": false,
+  "website_show_member_colors": null,
+  "website_custom_css": null,
+  "discord_nickname_guilds": [
+    {
+      "guild_id": "1234",
+      "template": "{names} | The Crew"
+    }
+  ],
+  "vrchat_osc_host": null,
+  "vrchat_osc_port": 9001,
+  "vrchat_osc_avatar_parameters": null,
+  "vrchat_avatars": [
+    {
+      "fronter": "Alice",
+      "avatar_id": "avtr_1234"
+    }
+  ],
+  "vrchat_avatar_cofront_policy": "Longest",
+  "vrchat_bio_template": "Fronting: {names}",
+  "vrchat_status_states": [
+    {
+      "fronter": "Sleeping",
+      "state": "Busy"
+    }
+  ],
   "enable_discord_status_message": true,
   "enable_vrchat": false,
+  "enable_website": true,
+  "enable_discord_nickname": null,
+  "enable_vrchat_osc": true,
   "simply_plural_token": {
     "secret": "sp_token_123"
   },
+  "pluralkit_token": null,
   "discord_status_message_token": {
     "secret": "discord_status_message_token_abc"
   },
   "vrchat_username": null,
   "vrchat_password": null,
-  "vrchat_cookie": null
+  "vrchat_cookie": null,
+  "website_access_token": null
 }"#;
 
         assert_eq!(json_string, expected_json);
@@ -1,5 +1,6 @@
 use crate::database;
 use crate::http::HttpResult;
+use crate::updater;
 use crate::users::config;
 use crate::users::jwt;
 use rocket::{serde::json::Json, State};
@@ -24,6 +25,7 @@ pub async fn post_api_user_config(
     db_pool: &State<PgPool>,
     app_user_secrets: &State<database::ApplicationUserSecrets>,
     client: &State<reqwest::Client>,
+    shared_updaters: &State<updater::UpdaterManager>,
 ) -> HttpResult<()> {
     let user_id = jwt?.user_id()?;
 
@@ -31,8 +33,15 @@ pub async fn post_api_user_config(
     let (_, valid_db_config) =
         config::create_config_with_strong_constraints(&user_id, client, &config)?;
 
-    let () = database::set_user_config_secrets(db_pool, user_id, valid_db_config, app_user_secrets)
-        .await?;
+    let () = database::set_user_config_secrets(
+        db_pool,
+        user_id.clone(),
+        valid_db_config,
+        app_user_secrets,
+    )
+    .await?;
+
+    let () = shared_updaters.invalidate_website_config(&user_id)?;
 
     Ok(())
 }
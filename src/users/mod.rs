@@ -5,8 +5,10 @@ mod config_macro;
 mod jwt;
 mod model;
 pub mod user_api;
+mod website_slug;
 
 pub use auth::*;
 pub use config::*;
 pub use jwt::*;
 pub use model::*;
+pub use website_slug::*;
@@ -3,12 +3,16 @@ use crate::http::HttpResult;
 use crate::users::auth;
 use crate::users::jwt;
 use crate::users::model::{Email, UserId};
+use crate::users::website_slug::WebsiteSlug;
 use rocket::response;
 use rocket::{serde::json::Json, State};
 use serde::Deserialize;
 use serde::Serialize;
 use sqlx::PgPool;
 
+/// How long a previous website slug keeps redirecting to the new one.
+const WEBSITE_SLUG_REDIRECT_GRACE_DAYS: i32 = 30;
+
 #[post("/api/user/register", data = "<credentials>")]
 pub async fn post_api_user_register(
     db_pool: &State<PgPool>,
@@ -46,10 +50,35 @@ pub async fn get_api_user_info(
     jwt: HttpResult<jwt::Jwt>,
 ) -> HttpResult<Json<UserInfoUI>> {
     let user_id = jwt?.user_id()?;
+    let website_slug = database::get_website_slug(db_pool, &user_id).await?;
     let user_info = database::get_user_info(db_pool, user_id)
         .await
         .map_err(response::Debug)?;
-    Ok(Json(user_info.into()))
+    Ok(Json(UserInfoUI {
+        website_slug,
+        ..user_info.into()
+    }))
+}
+
+#[post("/api/user/website_slug", data = "<request>")]
+pub async fn post_api_user_website_slug(
+    db_pool: &State<PgPool>,
+    jwt: HttpResult<jwt::Jwt>,
+    request: Json<WebsiteSlugRequest>,
+) -> HttpResult<()> {
+    let user_id = jwt?.user_id()?;
+
+    // validate here, such that invalid slugs are reported with their reason
+    let slug = WebsiteSlug::try_from(request.into_inner().slug)?;
+
+    database::set_website_slug(db_pool, &user_id, &slug, WEBSITE_SLUG_REDIRECT_GRACE_DAYS)
+        .await
+        .map_err(response::Debug)
+}
+
+#[derive(Deserialize)]
+pub struct WebsiteSlugRequest {
+    pub slug: String,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -57,6 +86,7 @@ pub struct UserInfoUI {
     pub id: UserId,
     pub email: Email,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    pub website_slug: Option<WebsiteSlug>,
 }
 
 impl From<database::UserInfo> for UserInfoUI {
@@ -71,6 +101,7 @@ impl From<database::UserInfo> for UserInfoUI {
             id,
             email,
             created_at,
+            website_slug: None,
         }
     }
 }
@@ -0,0 +1,99 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+const SLUG_MIN_LENGTH: usize = 3;
+const SLUG_MAX_LENGTH: usize = 40;
+
+/// Slugs which would be confusing or collide with our own routes and pages.
+const RESERVED_SLUGS: [&str; 20] = [
+    "about", "admin", "api", "assets", "config", "edit", "fronting", "help", "history", "login",
+    "logout", "new", "register", "settings", "static", "stats", "status", "user", "users", "www",
+];
+
+/// The public, URL-safe name of a users fronting website, as in `/fronting/<slug>`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct WebsiteSlug {
+    pub inner: String,
+}
+
+impl TryFrom<String> for WebsiteSlug {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        let slug = value.trim().to_lowercase();
+
+        let length = slug.chars().count();
+        if !(SLUG_MIN_LENGTH..=SLUG_MAX_LENGTH).contains(&length) {
+            return Err(anyhow!(
+                "Slug must be between {SLUG_MIN_LENGTH} and {SLUG_MAX_LENGTH} characters long."
+            ));
+        }
+
+        if !slug
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        {
+            return Err(anyhow!(
+                "Slug may only contain letters a-z, digits 0-9 and hyphens '-'."
+            ));
+        }
+
+        if slug.starts_with('-') || slug.ends_with('-') || slug.contains("--") {
+            return Err(anyhow!(
+                "Slug may not start or end with a hyphen or contain consecutive hyphens."
+            ));
+        }
+
+        if RESERVED_SLUGS.contains(&slug.as_str()) {
+            return Err(anyhow!("Slug '{slug}' is reserved."));
+        }
+
+        Ok(Self { inner: slug })
+    }
+}
+
+impl From<WebsiteSlug> for String {
+    fn from(slug: WebsiteSlug) -> Self {
+        slug.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slug(s: &str) -> Result<WebsiteSlug> {
+        WebsiteSlug::try_from(s.to_owned())
+    }
+
+    #[test]
+    fn test_valid_slugs_are_normalized() {
+        assert_eq!(
+            slug("my-system").ok().map(|s| s.inner).as_deref(),
+            Some("my-system")
+        );
+        assert_eq!(
+            slug(" Ayake42 ").ok().map(|s| s.inner).as_deref(),
+            Some("ayake42")
+        );
+    }
+
+    #[test]
+    fn test_invalid_slugs_are_rejected() {
+        for invalid in [
+            "ab",
+            &"a".repeat(SLUG_MAX_LENGTH + 1),
+            "my system",
+            "my_system",
+            "sÿstem",
+            "-system",
+            "system-",
+            "my--system",
+            "api",
+            "Fronting",
+        ] {
+            assert!(slug(invalid).is_err(), "'{invalid}' should be invalid");
+        }
+    }
+}
@@ -75,7 +75,7 @@ main() {
     check_updater_failure
     check_updater_loop_continues
     check_updater "DiscordStatusMessage" "Running"
-    get_updater_statuses | jq -r ".VRChat" | grep -q "Error"
+    get_updater_statuses | jq -r ".VRChat.status" | grep -q "Error"
     reset_changed_variables
 
 
@@ -104,7 +104,7 @@ check_updater() {
     PLATFORM="$1"
     STATUS="$2"
     echo "Check $PLATFORM is $STATUS ?"
-    RES="$( get_updater_statuses | jq ".$PLATFORM == \"$STATUS\"" )"
+    RES="$( get_updater_statuses | jq ".$PLATFORM.status == \"$STATUS\"" )"
     [[ "$RES" == "true" ]]    
 }
 
@@ -16,6 +16,8 @@ main() {
 
     start_webserver
 
+    set_website_slug
+
     set_system_fronts_set "A"
 
     check_system_fronts_set "A"
@@ -31,15 +33,24 @@ main() {
     echo "✅✅✅ Webserver Integration Test ✅✅✅"
 }
 
+set_website_slug() {
+    echo "Setting website slug ..."
+    curl -s --fail-with-body \
+        -H "Content-Type: application/json" \
+        -H "Authorization: Bearer $JWT" \
+        -d "{ \"slug\": \"$WEBSITE_SLUG\" }" \
+        "$BASE_URL/api/user/website_slug"
+}
+
 check_system_fronts_set() {
     SET="$1"
 
-    HTML="$(curl -s --fail-with-body "$BASE_URL/api/fronting/$USER_ID")"
+    HTML="$(curl -s --fail-with-body "$BASE_URL/fronting/$WEBSITE_SLUG")"
 
     if [[ "$SET" == "A" ]]; then
         grep '<title>SP-Updater-Test - Fronting Status</title>' <<< "$HTML"
-        grep '<div><img src="https://example.com/a" /><p>Annalea 💖 A.</p></div>' <<< "$HTML"
-        grep '<div><img src="https://example.com/b" /><p>Borgnen 👍 B.</p></div>' <<< "$HTML"
+        grep "<div><img src=\"/fronting/$WEBSITE_SLUG/avatars/$ANNALEA_ID\" /><p>Annalea 💖 A.</p></div>" <<< "$HTML"
+        grep "<div><img src=\"/fronting/$WEBSITE_SLUG/avatars/$BORGNEN_ID\" /><p>Borgnen 👍 B.</p></div>" <<< "$HTML"
         grep '<div><img src="" /><p>Daenssa 📶 D.</p></div>' <<< "$HTML"
         grep '<div><img src="" /><p>Cstm First</p></div>' <<< "$HTML"
         [[ "$( grep '<div>' <<< "$HTML" | wc -l )" == "4" ]]
@@ -55,8 +66,10 @@ check_system_fronts_set() {
 export BASE_URL="http://localhost:8000"
 
 SYSTEM_PUBLIC_NAME="SP-Updater-Test"
+WEBSITE_SLUG="sp-updater-test"
 ENABLE_DISCORD_STATUS_MESSAGE=false
 ENABLE_VRCHAT=false
+ENABLE_WEBSITE=true
 unset DISCORD_STATUS_MESSAGE_TOKEN
 unset VRCHAT_USERNAME
 unset VRCHAT_PASSWORD